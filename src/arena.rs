@@ -0,0 +1,110 @@
+use crate::{calculation::Calculation, operator::Operator};
+
+/// Index of a [`Node`] in an [`Arena`].
+pub type NodeId = u32;
+
+/// Intermediate result of a solve. Unlike a [`Calculation`] it refers to
+/// its operands by their index in the arena, so combining two results does
+/// not copy them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Node {
+    Binary(Operator, NodeId, NodeId),
+    Unary(Operator, NodeId),
+    Cube(usize, u64),
+}
+
+/// Every intermediate result of one solve. Results that are part of several
+/// larger ones are stored once, [`Calculation`] trees are only built for
+/// the results that are reported.
+#[derive(Debug, Clone, Default)]
+pub struct Arena {
+    nodes: Vec<Node>,
+}
+
+impl Arena {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, node: Node) -> NodeId {
+        let id = NodeId::try_from(self.nodes.len()).expect("Zu viele Zwischenergebnisse");
+        self.nodes.push(node);
+        id
+    }
+
+    /// Overwrites the node `id`, which no other node may refer to yet.
+    pub fn replace(&mut self, id: NodeId, node: Node) {
+        self.nodes[id as usize] = node;
+    }
+
+    pub fn get(&self, id: NodeId) -> Node {
+        self.nodes[id as usize]
+    }
+
+    /// The calculation of the node `id` as a tree.
+    pub fn calculation(&self, id: NodeId) -> Calculation {
+        match self.get(id) {
+            Node::Binary(op, a, b) => {
+                Calculation::binary(op, self.calculation(a), self.calculation(b))
+            }
+            Node::Unary(op, a) => Calculation::unary(op, self.calculation(a)),
+            Node::Cube(i, value) => Calculation::Cube(i, value),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Removes all nodes, keeping the allocated memory for the next solve.
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_nodes_are_stored_once() {
+        let mut arena = Arena::new();
+        let six = arena.push(Node::Cube(0, 6));
+        let four = arena.push(Node::Cube(1, 4));
+        let sum = arena.push(Node::Binary(Operator::Add, six, four));
+        let product = arena.push(Node::Binary(Operator::Mul, sum, sum));
+        let root = arena.push(Node::Unary(Operator::Sqrt, product));
+
+        assert_eq!(arena.len(), 5);
+        assert_eq!(
+            arena.calculation(product).to_string(),
+            "((6 + 4) * (6 + 4))"
+        );
+        assert_eq!(arena.calculation(root).to_string(), "√((6 + 4) * (6 + 4))");
+        assert_eq!(arena.get(sum), Node::Binary(Operator::Add, six, four));
+
+        arena.clear();
+        assert!(arena.is_empty());
+        assert_eq!(arena.push(Node::Cube(0, 1)), 0);
+    }
+
+    #[test]
+    fn the_last_replacement_wins() {
+        let mut arena = Arena::new();
+        let six = arena.push(Node::Cube(0, 6));
+        let four = arena.push(Node::Cube(1, 4));
+        let result = arena.push(Node::Binary(Operator::Add, six, four));
+
+        arena.replace(result, Node::Binary(Operator::Sub, six, four));
+        arena.replace(result, Node::Binary(Operator::Mul, six, four));
+        let total = arena.push(Node::Unary(Operator::Factorial, result));
+
+        assert_eq!(arena.len(), 4);
+        assert_eq!(arena.get(result), Node::Binary(Operator::Mul, six, four));
+        assert_eq!(arena.calculation(total).to_string(), "(6 * 4)!");
+    }
+}
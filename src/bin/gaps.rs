@@ -1,80 +1,116 @@
 use itertools::Itertools;
-use nums::fastgame;
+use nums::{die::Die, reach::Reach, rules::Rules, table::Table};
 use rayon::prelude::*;
 use std::io::Write;
-use std::ops::Range;
 
 fn main() {
-    let args = std::env::args().collect::<Vec<String>>();
+    let mut args = std::env::args().collect::<Vec<String>>();
+
+    let mut rules = Rules::default();
+    if let Some(i) = args.iter().position(|arg| arg == "--die") {
+        let die = match args.get(i + 1).map(|die| die.parse::<Die>()) {
+            Some(Ok(die)) => die,
+            Some(Err(e)) => {
+                eprintln!("{}", e);
+                return;
+            }
+            None => {
+                eprintln!("Please provide a die after --die.");
+                return;
+            }
+        };
+        if die.faces().iter().any(|&face| face > u8::MAX.into()) {
+            eprintln!("Faces larger than {} are not supported.", u8::MAX);
+            return;
+        }
+        rules.die = die;
+        args.drain(i..=i + 1);
+    }
 
-    if args.len() != 2 {
-        eprintln!("Usage: {} <number>", args[0]);
+    let mut table_file = None;
+    if let Some(i) = args.iter().position(|arg| arg == "--table") {
+        let Some(file) = args.get(i + 1) else {
+            eprintln!("Please provide a file after --table.");
+            return;
+        };
+        table_file = Some(file.clone());
+        args.drain(i..=i + 1);
+    }
+
+    if args.len() != 2 && args.len() != 3 {
+        eprintln!(
+            "Usage: {} <number> [max multiplier exponent] [--die <die>] [--table <file>]",
+            args[0]
+        );
         return;
     }
 
-    let cube_count = match args[1].parse::<u8>() {
-        Ok(3) => 3,
-        Ok(4) => 4,
+    let cube_count = match args[1].parse::<usize>() {
+        Ok(n) if n >= 2 => n,
         _ => {
-            eprintln!("Please provide either 3 or 4 cubes.");
+            eprintln!("Please provide at least 2 cubes.");
             return;
         }
     };
+    if value_range(cube_count).is_none() {
+        eprintln!(
+            "The targets of {} cubes do not fit into 64 bits.",
+            cube_count
+        );
+        return;
+    }
+
+    let mut result_file = format!("gaps_{}.txt", cube_count);
+    if let Some(arg) = args.get(2) {
+        let Ok(max_exponent) = arg.parse::<u32>() else {
+            eprintln!("Please provide the largest exponent of the multipliers 1, 10, 100, ...");
+            return;
+        };
+        rules.multipliers = Rules::powers_of_ten(max_exponent);
+        result_file = format!("gaps_{}_x{}.txt", cube_count, max_exponent);
+    }
+    if rules.die != Die::d6() {
+        let die = rules
+            .die
+            .to_string()
+            .replace(['[', ']'], "")
+            .replace(',', "-");
+        result_file = result_file.replace(".txt", &format!("_{}.txt", die));
+    }
 
     let time = std::time::Instant::now();
-    let mut gaps = match cube_count {
-        3 => {
-            println!("Finding distances for 3 cubes...");
-            find_3_distances()
-        }
-        4 => {
-            println!("Finding distances for 4 cubes...");
-            find_4_distances()
-        }
-        _ => unreachable!(),
+    println!(
+        "Finding distances for {} cubes of {}...",
+        cube_count, rules.die
+    );
+    let mut gaps = match table_file {
+        Some(file) => match table_distances(cube_count, &rules, &file) {
+            Ok(gaps) => gaps,
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
+            }
+        },
+        None => find_distances(cube_count, &rules),
     };
 
     let elapsed = time.elapsed();
     println!("Elapsed time: {:.2?}", elapsed);
 
-    gaps.sort_by(|a, b| b.distance.cmp(&a.distance));
+    gaps.sort_by_key(|gap| std::cmp::Reverse(gap.distance));
 
-    let result_file = format!("gaps_{}.txt", cube_count);
     let mut file = std::fs::File::create(result_file).expect("Failed to create file");
     for gap in gaps.iter() {
-        // let perm = permutation_from_index(gap.i);
-        // let permutation = match cube_count {
-        //     3 => &perm[..3],
-        //     4 => &perm,
-        //     _ => unreachable!(),
-        // };
-
-        let permutation = match cube_count {
-            3 => &gap.permutation[..3],
-            4 => &gap.permutation,
-            _ => unreachable!(),
-        };
         writeln!(
             &mut file,
-            "{permutation:?} {} {} {} {:?}",
-            gap.distance, gap.midpoint, gap.closest, gap.solutions
+            "{:?} {} {} {} {:?}",
+            gap.permutation, gap.distance, gap.midpoint, gap.closest, gap.solutions
         )
         .expect("Failed to write to file");
     }
 }
 
-fn permutation_from_index(mut index: usize) -> [u8; 4] {
-    let mut permutation = [0u8; 4];
-
-    for i in 0..4 {
-        permutation[i] = (index % 6) as u8 + 1; // 1 to 6
-        index /= 6;
-    }
-
-    permutation
-}
-
-fn find_max_midpoint(solutions: &[u16], min: u16, max: u16) -> (u16, u16, u16) {
+fn find_max_midpoint(solutions: &[u64], min: u64, max: u64) -> (u64, u64, u64) {
     if solutions.is_empty() {
         panic!("No solutions found");
     }
@@ -101,7 +137,7 @@ fn find_max_midpoint(solutions: &[u16], min: u16, max: u16) -> (u16, u16, u16) {
     }
 
     // first
-    let a = solutions.first().unwrap().clone();
+    let a = *solutions.first().unwrap();
     let b = min;
     let midpoint = a.saturating_add(b) / 2;
     let min_distance = b.saturating_sub(a) / 2;
@@ -116,7 +152,7 @@ fn find_max_midpoint(solutions: &[u16], min: u16, max: u16) -> (u16, u16, u16) {
     }
 
     // last
-    let a = solutions.last().unwrap().clone();
+    let a = *solutions.last().unwrap();
     let b = max;
     let midpoint = a.saturating_add(b) / 2;
     let min_distance = b.saturating_sub(a) / 2;
@@ -134,143 +170,69 @@ fn find_max_midpoint(solutions: &[u16], min: u16, max: u16) -> (u16, u16, u16) {
 }
 
 struct GapResult {
-    permutation: [u8; 4],
-    solutions: Vec<u16>,
-    midpoint: u16,
-    distance: u16,
-    closest: u16,
+    permutation: Vec<u8>,
+    solutions: Vec<u64>,
+    midpoint: u64,
+    distance: u64,
+    closest: u64,
 }
 
-// fn find_4_distances() -> Vec<GapResult> {
-//     let permutation_count = 6 * 6 * 6 * 6; // 1296 permutations for 4 dice (1-6)
-//     let min_value = 100;
-//     let max_value = 1000;
-//
-//     let mut distances: Vec<GapResult> = Vec::with_capacity(permutation_count);
-//     for i in 0..permutation_count {
-//         distances.push(GapResult {
-//             i,
-//             solutions: Vec::new(),
-//             midpoint: 0,
-//             distance: 0,
-//             closest: 0,
-//         });
-//     }
-//
-//     distances.par_iter_mut().enumerate().for_each_init(
-//         || fastgame::Game::new(fastgame::DiceAmount::Four),
-//         |game, (i, gap_result)| {
-//             let permutation = permutation_from_index(i);
-//
-//             game.solve_four_fast(min_value, max_value, permutation, &mut gap_result.solutions);
-//
-//             let (mid, distance, closest) =
-//                 find_max_midpoint(&gap_result.solutions, min_value, max_value);
-//             gap_result.midpoint = mid;
-//             gap_result.distance = distance;
-//             gap_result.closest = closest;
-//
-//             println!("Permutation: {permutation:?}, {mid} {distance} {closest}");
-//         },
-//     );
-//
-//     distances
-// }
-//
-// fn find_3_distances() -> Vec<GapResult> {
-//     let permutation_count = 6 * 6 * 6; // 216 permutations for 3 dice (1-6)
-//     let min_value = 0;
-//     let max_value = 100;
-//
-//     let mut permutations: Vec<GapResult> = Vec::with_capacity(permutation_count);
-//     for i in 0..permutation_count {
-//         permutations.push(GapResult {
-//             i,
-//             solutions: Vec::new(),
-//             midpoint: 0,
-//             distance: 0,
-//             closest: 0,
-//         });
-//     }
-//
-//     permutations.par_iter_mut().enumerate().for_each_init(
-//         || fastgame::Game::new(fastgame::DiceAmount::Three),
-//         |game, (i, gap_result)| {
-//             let permutation = permutation_from_index(i);
-//
-//             game.solve_three_fast(min_value, max_value, permutation, &mut gap_result.solutions);
-//
-//             let (mid, distance, closest) =
-//                 find_max_midpoint(&gap_result.solutions, min_value, max_value);
-//
-//             gap_result.midpoint = mid;
-//             gap_result.distance = distance;
-//             gap_result.closest = closest;
-//
-//             println!(
-//                 "Permutation: {:?} {i}, {mid} {distance} {closest}",
-//                 &permutation[0..3]
-//             );
-//         },
-//     );
-//
-//     permutations
-// }
-
-fn find_4_distances() -> Vec<GapResult> {
-    // let min_value = 100;
-    let min_value = 0;
-    let max_value = 1000;
-
-    let sides = 1..=6;
-    let combos = sides.clone().combinations_with_replacement(4);
-    let mut permutations: Vec<GapResult> = Vec::with_capacity(126);
-    for perm in combos {
-        permutations.push(GapResult {
-            permutation: [perm[0] as u8, perm[1] as u8, perm[2] as u8, perm[3] as u8],
-            solutions: Vec::new(),
-            midpoint: 0,
-            distance: 0,
-            closest: 0,
-        });
-    }
-
-    permutations.par_iter_mut().for_each_init(
-        || fastgame::Game::new(fastgame::DiceAmount::Four),
-        |game, gap_result| {
-            game.solve_four_fast(
-                min_value,
-                max_value,
-                gap_result.permutation,
-                &mut gap_result.solutions,
-            );
-
-            let (mid, distance, closest) =
-                find_max_midpoint(&gap_result.solutions, min_value, max_value);
-            gap_result.midpoint = mid;
-            gap_result.distance = distance;
-            gap_result.closest = closest;
+/// 0..100 for three cubes, 0..1000 for four cubes, ... `None` if the end
+/// does not fit into a u64.
+fn value_range(cube_count: usize) -> Option<(u64, u64)> {
+    let exponent = u32::try_from(cube_count - 1).ok()?;
+    Some((0, 10u64.checked_pow(exponent)?))
+}
 
-            println!(
-                "Permutation: {:?}, {mid} {distance} {closest}",
-                gap_result.permutation
-            );
-        },
-    );
+/// Reads the reachable values from the table in `file`. If there is no
+/// table for these rules yet, it is built and written to `file` first.
+fn table_distances(cube_count: usize, rules: &Rules, file: &str) -> Result<Vec<GapResult>, String> {
+    let (min_value, max_value) = value_range(cube_count).expect("cube count was checked");
+    let range = min_value..max_value;
+
+    let table = match Table::load(file) {
+        Ok(table) if table.fits(cube_count, rules) && table.range == range => table,
+        Ok(_) => return Err(format!("{} was built for other rules.", file)),
+        Err(_) => {
+            println!("Building table {}...", file);
+            let table = Table::build(cube_count, rules.clone(), range);
+            table
+                .save(file)
+                .map_err(|e| format!("Failed to write {}: {}", file, e))?;
+            table
+        }
+    };
 
-    permutations
+    let sides = rules.die.faces().iter().map(|&face| face as u8);
+    let gaps = sides
+        .combinations_with_replacement(cube_count)
+        .map(|perm| {
+            let dices = perm.iter().map(|&d| d.into()).collect::<Vec<u64>>();
+            let solutions = table.reachable(&dices).unwrap_or_default();
+            let (midpoint, distance, closest) = find_max_midpoint(&solutions, min_value, max_value);
+
+            GapResult {
+                permutation: perm,
+                solutions,
+                midpoint,
+                distance,
+                closest,
+            }
+        })
+        .collect();
+
+    Ok(gaps)
 }
 
-fn find_3_distances() -> Vec<GapResult> {
-    let min_value = 0;
-    let max_value = 100;
+fn find_distances(cube_count: usize, rules: &Rules) -> Vec<GapResult> {
+    let (min_value, max_value) = value_range(cube_count).expect("cube count was checked");
 
-    let sides = 1..=6;
-    let combos = sides.clone().combinations_with_replacement(3);
-    let mut permutations: Vec<GapResult> = Vec::with_capacity(56);
+    let sides = rules.die.faces().iter().map(|&face| face as u8);
+    let combos = sides.combinations_with_replacement(cube_count);
+    let mut permutations: Vec<GapResult> = Vec::new();
     for perm in combos {
         permutations.push(GapResult {
-            permutation: [perm[0] as u8, perm[1] as u8, perm[2] as u8, 0],
+            permutation: perm,
             solutions: Vec::new(),
             midpoint: 0,
             distance: 0,
@@ -278,29 +240,27 @@ fn find_3_distances() -> Vec<GapResult> {
         });
     }
 
-    permutations.par_iter_mut().for_each_init(
-        || fastgame::Game::new(fastgame::DiceAmount::Three),
-        |game, gap_result| {
-            game.solve_three_fast(
-                min_value,
-                max_value,
-                gap_result.permutation,
-                &mut gap_result.solutions,
-            );
-
-            let (mid, distance, closest) =
-                find_max_midpoint(&gap_result.solutions, min_value, max_value);
-
-            gap_result.midpoint = mid;
-            gap_result.distance = distance;
-            gap_result.closest = closest;
-
-            println!(
-                "Permutation: {:?}, {mid} {distance} {closest}",
-                &gap_result.permutation[0..3]
-            );
-        },
-    );
+    let reach = Reach::new(rules.clone());
+    permutations.par_iter_mut().for_each(|gap_result| {
+        let dices = gap_result
+            .permutation
+            .iter()
+            .map(|&d| d.into())
+            .collect::<Vec<u64>>();
+        let coverage = reach.coverage(min_value..max_value, &dices);
+        gap_result.solutions = coverage.iter().collect();
+
+        let (mid, distance, closest) =
+            find_max_midpoint(&gap_result.solutions, min_value, max_value);
+        gap_result.midpoint = mid;
+        gap_result.distance = distance;
+        gap_result.closest = closest;
+
+        println!(
+            "Permutation: {:?}, {mid} {distance} {closest}",
+            gap_result.permutation
+        );
+    });
 
     permutations
 }
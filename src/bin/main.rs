@@ -1,26 +1,133 @@
-use std::io::Write;
+use std::{io::Write, time::Duration};
 
 use anyhow::{anyhow, Result};
-use nums::game::{DiceAmount, Game};
+use nums::{
+    daily,
+    die::Die,
+    difficulty::{Difficulty, Level},
+    game::{Answer, Game, MAX_ATTEMPTS},
+    operator::Operator,
+    rules::Rules,
+    solver::CancelToken,
+    table::Table,
+};
+use rand::{rngs::StdRng, SeedableRng};
 
 fn main() {
-    let args = std::env::args().collect::<Vec<String>>();
+    let mut args = std::env::args().collect::<Vec<String>>();
+    let all_solutions = take_flag(&mut args, "--all");
+    let keep_equivalent = take_flag(&mut args, "--raw");
+    let play_mode = take_flag(&mut args, "--play");
+    let difficulty = match take_option(&mut args, "--difficulty").map(|d| d.parse::<Difficulty>()) {
+        Some(Ok(difficulty)) => difficulty,
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            return;
+        }
+        None => Difficulty::default(),
+    };
+
+    let level = match take_option(&mut args, "--level").map(|l| l.parse::<Level>()) {
+        Some(Ok(level)) => Some(level),
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            return;
+        }
+        None => None,
+    };
+
+    let seed = match take_option(&mut args, "--seed").map(|s| s.parse::<u64>()) {
+        Some(Ok(seed)) => Some(seed),
+        Some(Err(_)) => {
+            eprintln!("Der Startwert muss eine Zahl sein");
+            return;
+        }
+        None => None,
+    };
+    let daily = take_flag(&mut args, "--daily");
+
+    let first = match take_option(&mut args, "--first").map(|n| n.parse::<usize>()) {
+        Some(Ok(first)) => Some(first),
+        Some(Err(_)) => {
+            eprintln!("Die Anzahl der Lösungen muss eine Zahl sein");
+            return;
+        }
+        None => None,
+    };
+
+    let timeout = match take_option(&mut args, "--timeout").map(|t| t.parse::<f64>()) {
+        Some(Ok(timeout)) if timeout >= 0.0 && timeout.is_finite() => {
+            Some(Duration::from_secs_f64(timeout))
+        }
+        Some(_) => {
+            eprintln!("Das Zeitlimit muss eine Zahl von Sekunden sein");
+            return;
+        }
+        None => None,
+    };
+
+    let rules = match args_to_rules(&mut args) {
+        Ok(rules) => rules,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+
+    let table = match take_option(&mut args, "--table").map(Table::load) {
+        Some(Ok(table)) => Some(table),
+        Some(Err(e)) => {
+            eprintln!("Die Tabelle kann nicht gelesen werden: {}", e);
+            return;
+        }
+        None => None,
+    };
+
+    // the time limit starts before generating a game, which solves games too
+    let cancel = timeout.map(CancelToken::with_timeout).unwrap_or_default();
+    let game = args_to_game(args, rules, level, seed, daily, table.as_ref(), &cancel);
+    if let Ok(mut game) = game {
+        game.all_solutions = all_solutions;
+        game.keep_equivalent = keep_equivalent;
+        game.difficulty = difficulty;
+        game.cancel = cancel;
+
+        if play_mode {
+            play(&mut game);
+            return;
+        }
+
+        if let Some(first) = first {
+            print_first(&game, first);
+            return;
+        }
 
-    if let Ok(mut game) = args_to_game(args) {
         game.print_game();
         game.solve();
-        game.solutions.sort();
+        game.sort_solutions();
+        print_incomplete(&game);
 
         if get_yn_input("Anzahl der gefundenen Lösungen anzeigen (j/n)? ").is_err() {
             return;
         }
 
         game.print_solution_amount();
+        game.print_rating();
 
-        if game.solutions.len() == 0 {
+        if game.solutions.is_empty() {
+            if get_yn_input("Nächste erreichbare Ergebnisse anzeigen (j/n)? ").is_ok() {
+                game.print_closest();
+            }
             return;
         }
 
+        for (i, hint) in game.hints().iter().enumerate() {
+            if get_yn_input(&format!("Hinweis {} anzeigen (j/n)? ", i + 1)).is_err() {
+                break;
+            }
+            println!("\n{}\n", hint);
+        }
+
         if get_yn_input("Lösungen anzeigen (j/n)? ").is_err() {
             return;
         }
@@ -29,84 +136,304 @@ fn main() {
     }
 }
 
-fn args_to_game(args: Vec<String>) -> Result<Game> {
-    return match args.len() {
-        1 => {
-            return Err(anyhow!("Bitte gib eine Zahl an, die erreicht werden soll."));
-        }
-        2 => {
-            let amount_cubes = match args[1].parse::<u8>().unwrap() {
-                3 => DiceAmount::Three,
-                4 => DiceAmount::Four,
-                _ => {
-                    return Err(anyhow!("Nur 3 oder 4 Würfel sind erlaubt."));
-                }
-            };
-            Ok(Game::new(amount_cubes))
-        }
-        3 => {
-            let amount_cubes = match args[1].parse::<u8>().unwrap() {
-                3 => DiceAmount::Three,
-                4 => DiceAmount::Four,
-                _ => {
-                    return Err(anyhow!("Nur 3 oder 4 Würfel sind erlaubt."));
-                }
-            };
-            let num = args[2]
-                .parse::<u64>()
-                .expect("Argument <Nummer> muss eine Zahl sein");
+/// Prints the first `amount` solutions as soon as they are found and stops
+/// the search then.
+fn print_first(game: &Game, amount: usize) {
+    game.print_game();
+
+    let mut solutions = game.solutions_iter();
+    let mut found = 0;
+    for solution in solutions.by_ref().take(amount) {
+        println!("\t{} = {}", solution, game.num);
+        found += 1;
+    }
 
-            Ok(Game::of_number(amount_cubes, num))
+    // the search only ran to its end if fewer solutions were found
+    let incomplete = found < amount && solutions.finish().incomplete;
+    match found {
+        _ if incomplete => println!("\nZeitlimit erreicht, {} Lösungen ausgegeben\n", found),
+        0 => println!("\nKeine Lösung gefunden\n"),
+        found if found < amount => println!("\nAlle {} Lösungen ausgegeben\n", found),
+        found => println!("\nDie ersten {} Lösungen ausgegeben\n", found),
+    }
+}
+
+fn print_incomplete(game: &Game) {
+    if game.incomplete {
+        println!("\nZeitlimit erreicht, die Suche ist unvollständig\n");
+    }
+}
+
+/// Lets the player enter answers until one is correct or they give up, then
+/// offers the solutions of the solver for comparison.
+fn play(game: &mut Game) {
+    game.print_game();
+    game.solve();
+    game.sort_solutions();
+    print_incomplete(game);
+
+    let hints = game.hints();
+    let mut next_hint = 0;
+
+    while let Some(input) =
+        get_line_input("Deine Lösung (? für einen Hinweis, leer zum Aufgeben): ")
+    {
+        if input.is_empty() {
+            break;
         }
 
-        6 | 7 => {
-            let amount_cubes = match args[1].parse::<u8>().unwrap() {
-                3 => DiceAmount::Three,
-                4 => DiceAmount::Four,
-                _ => {
-                    return Err(anyhow!("Nur 3 oder 4 Würfel sind erlaubt."));
-                }
+        if input == "?" {
+            match hints.get(next_hint) {
+                Some(hint) => println!("\n{}\n", hint),
+                None if hints.is_empty() => println!("\n{}\n", no_solution(game)),
+                None => println!("\nKeine weiteren Hinweise\n"),
+            }
+            next_hint += 1;
+            continue;
+        }
+
+        match game.check_answer(&input) {
+            Ok(Answer::Correct(calc)) => {
+                println!("\nRichtig! {} = {}\n", calc, game.num);
+                break;
+            }
+            Ok(Answer::Wrong {
+                calc,
+                value,
+                distance,
+            }) => {
+                println!(
+                    "\nLeider falsch: {} = {}, das sind {} daneben\n",
+                    calc, value, distance
+                );
+            }
+            Err(e) => println!("\nUngültige Lösung: {}\n", e),
+        }
+    }
+
+    if get_yn_input("Lösungen zum Vergleich anzeigen (j/n)? ").is_err() {
+        return;
+    }
+
+    match game.solutions.len() {
+        0 => {
+            println!("\n{}", no_solution(game));
+            game.print_closest();
+        }
+        1 => println!("\nEinzige Lösung: {}\n", game.solutions[0]),
+        _ => game.print_easiest_and_hardest(),
+    }
+}
+
+fn no_solution(game: &Game) -> &'static str {
+    if game.incomplete {
+        "Bis zum Zeitlimit wurde keine Lösung gefunden."
+    } else {
+        "Es gibt keine Lösung."
+    }
+}
+
+/// Removes the options of the rules from `args` and returns the rules.
+fn args_to_rules(args: &mut Vec<String>) -> Result<Rules> {
+    let mut rules = Rules {
+        rational: take_flag(args, "--rational"),
+        negative: take_flag(args, "--negative"),
+        ..Rules::default()
+    };
+
+    if let Some(ops) = take_option(args, "--ops") {
+        rules.operators = parse_operators(&ops)?;
+    }
+    if let Some(multipliers) = take_option(args, "--multipliers") {
+        rules.multipliers = parse_multipliers(&multipliers)?;
+    }
+    if let Some(die) = take_option(args, "--die") {
+        rules.die = die.parse::<Die>()?;
+    }
+
+    Ok(rules)
+}
+
+fn args_to_game(
+    args: Vec<String>,
+    rules: Rules,
+    level: Option<Level>,
+    seed: Option<u64>,
+    daily: bool,
+    table: Option<&Table>,
+    cancel: &CancelToken,
+) -> Result<Game> {
+    if args.len() == 1 {
+        return Err(anyhow!("Bitte gib eine Zahl an, die erreicht werden soll."));
+    }
+
+    let amount_cubes = match args[1].parse::<usize>() {
+        Ok(n) if n >= 2 => n,
+        _ => {
+            return Err(anyhow!("Es sind mindestens 2 Würfel nötig."));
+        }
+    };
+
+    if let Some(table) = table.filter(|table| !table.fits(amount_cubes, &rules)) {
+        eprintln!(
+            "Die Tabelle ist für {} Würfel und passt nicht zu diesem Spiel, sie wird nicht benutzt",
+            table.num_dices
+        );
+    }
+
+    let seed = seed.unwrap_or_else(rand::random);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    match args.len() - 2 {
+        0 if daily => {
+            let today = daily::today();
+            println!("\nRätsel des Tages {}", daily::date(today));
+
+            Ok(Game::daily(amount_cubes, rules, today, cancel))
+        }
+        0 => {
+            let game = match level {
+                Some(level) => Game::generate(
+                    amount_cubes,
+                    level,
+                    rules,
+                    MAX_ATTEMPTS,
+                    table,
+                    cancel,
+                    &mut rng,
+                ),
+                None => match table {
+                    Some(table) => {
+                        Game::random_solvable(amount_cubes, rules, table, MAX_ATTEMPTS, &mut rng)
+                    }
+                    None => Game::random(amount_cubes, rules, &mut rng),
+                },
             };
+
+            Ok(Game {
+                seed: Some(seed),
+                ..game
+            })
+        }
+        _ if level.is_some() || daily => {
+            eprintln!("--level und --daily gehen nur ohne vorgegebene Zahl und Würfel");
+            Err(anyhow!(help()))
+        }
+        1 => {
             let num = args[2]
                 .parse::<u64>()
                 .expect("Argument <Nummer> muss eine Zahl sein");
 
-            let mut dices = [0; 4];
-            dices[0] = args[3]
-                .parse::<u64>()
-                .expect("Argument <Würfel1> muss eine Zahl sein");
-
-            dices[1] = args[4]
+            Ok(Game {
+                seed: Some(seed),
+                ..Game::roll(amount_cubes, num, rules, &mut rng)
+            })
+        }
+        n if n == amount_cubes + 1 => {
+            let num = args[2]
                 .parse::<u64>()
-                .expect("Argument <Würfel2> muss eine Zahl sein");
+                .expect("Argument <Nummer> muss eine Zahl sein");
 
-            dices[2] = args[5]
-                .parse::<u64>()
-                .expect("Argument <Würfel2> muss eine Zahl sein");
+            let dices = args[3..]
+                .iter()
+                .enumerate()
+                .map(|(i, arg)| {
+                    arg.parse::<u64>().unwrap_or_else(|_| {
+                        panic!("Argument <Würfel{}> muss eine Zahl sein", i + 1)
+                    })
+                })
+                .collect::<Vec<_>>();
 
-            if args.len() > 6 {
-                dices[3] = args[6]
-                    .parse::<u64>()
-                    .expect("Argument <Würfel2> muss eine Zahl sein");
+            if let Err(e) = rules.die.check(&dices) {
+                eprintln!("{}", e);
+                return Err(e);
             }
 
-            Ok(Game::of(amount_cubes, num, dices))
+            Ok(Game {
+                rules,
+                ..Game::of(num, dices)
+            })
         }
         _ => Err(anyhow!(help())),
-    };
+    }
+}
+
+/// Removes `flag` from `args` and returns whether it was present.
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let len = args.len();
+    args.retain(|arg| arg != flag);
+    args.len() != len
+}
+
+/// Removes `option` and the value following it from `args` and returns the
+/// value.
+fn take_option(args: &mut Vec<String>, option: &str) -> Option<String> {
+    let i = args.iter().position(|arg| arg == option)?;
+    args.remove(i);
+    if i < args.len() {
+        Some(args.remove(i))
+    } else {
+        None
+    }
+}
+
+/// Parses a comma separated list of operator names or symbols.
+fn parse_operators(ops: &str) -> Result<Vec<Operator>> {
+    ops.split(',').map(|op| op.trim().parse()).collect()
+}
+
+/// Parses a comma separated list of multipliers for the dice.
+fn parse_multipliers(multipliers: &str) -> Result<Vec<u64>> {
+    multipliers
+        .split(',')
+        .map(|m| match m.trim().parse::<u64>() {
+            Ok(m) if m > 0 => Ok(m),
+            _ => Err(anyhow!("Ungültiger Faktor: {}", m)),
+        })
+        .collect()
 }
 
 fn help() -> String {
     let mut help = String::new();
     help.push_str("Aufruf: ");
-    help.push_str("./nums <Nummer> <Würfel1> <Würfel2> <Würfel3> <Würfel4>\n");
+    help.push_str("./nums <Anzahl> <Nummer> <Würfel1> ... <WürfelN>\n");
     help.push_str("Möglichkeiten:\n");
-    help.push_str(" - 0 Argumente: Zufällige Zahl und Würfel\n");
-    help.push_str(" - 1 Argument: Vorgegebene Zahl und zufällige Würfel\n");
-    help.push_str(" - 5 Argumente: Vorgegebene Zahl und Würfel\n");
+    help.push_str(" - 1 Argument: Zufällige Zahl und <Anzahl> Würfel\n");
+    help.push_str(" - 2 Argumente: Vorgegebene Zahl und zufällige Würfel\n");
+    help.push_str(" - 2 + <Anzahl> Argumente: Vorgegebene Zahl und Würfel\n");
+    help.push_str("Optionen:\n");
+    help.push_str(" --play: Selbst lösen, die Eingabe wird geprüft\n");
+    help.push_str(" --first <Anzahl>: Die ersten gefundenen Lösungen sofort ausgeben\n");
+    help.push_str(" --timeout <Sekunden>: Suche nach dieser Zeit abbrechen\n");
+    help.push_str(" --all: Alle unterschiedlichen Lösungen suchen\n");
+    help.push_str(
+        " --raw: Gleichwertige Lösungen wie ((a + b) + c) und (a + (b + c)) einzeln zählen\n",
+    );
+    help.push_str(" --difficulty <Modell>: classic oder mental (Aufwand beim Kopfrechnen)\n");
+    help.push_str(" --level <Stufe>: Spiel mit Lösung erzeugen, easy, medium, hard oder expert\n");
+    help.push_str(" --seed <Zahl>: Startwert für wiederholbare Spiele\n");
+    help.push_str(" --daily: Rätsel des Tages, für alle am selben Tag gleich\n");
+    help.push_str(" --table <Datei>: Nur lösbare Spiele würfeln, Tabelle von gaps --table\n");
+    help.push_str(" --rational: Brüche als Zwischenergebnisse erlauben\n");
+    help.push_str(" --negative: Negative Zwischenergebnisse erlauben\n");
+    help.push_str(" --ops <Op1>,<Op2>,...: Erlaubte Rechenarten, z.B. add,sub,mul,div,pow,sqrt\n");
+    help.push_str(" --die <Würfel>: d4, d6, d8, d10 (0 bis 9), d12, d20 oder Seiten wie 1,2,3,5\n");
+    help.push_str(" --multipliers <F1>,<F2>,...: Erlaubte Faktoren der Würfel, z.B. 1,10,100\n");
     help
 }
 
+/// Reads a trimmed line, `None` at the end of the input.
+fn get_line_input(question: &str) -> Option<String> {
+    print!("{}", question);
+    std::io::stdout().flush().ok()?;
+
+    let mut line = String::new();
+    match std::io::stdin().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.trim().to_string()),
+    }
+}
+
 fn get_yn_input(question: &str) -> Result<()> {
     'outer: loop {
         print!("{}", question);
@@ -116,7 +443,7 @@ fn get_yn_input(question: &str) -> Result<()> {
 
         loop {
             let mut character = String::new();
-            if let Ok(_) = std::io::stdin().read_line(&mut character) {
+            if std::io::stdin().read_line(&mut character).is_ok() {
                 if character.len() != 2 {
                     continue 'outer;
                 }
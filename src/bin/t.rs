@@ -2,152 +2,94 @@ use std::io::Write;
 
 use anyhow::{anyhow, Result};
 use nums::fastgame;
-use nums::game::{DiceAmount, Game};
+use nums::game::Game;
+use nums::solver::{cross_check, Solver};
 
 fn main() {
     let args = std::env::args().collect::<Vec<String>>();
 
-    if let Ok(mut game) = args_to_game(args) {
-        game.print_game();
-        game.solve();
-        game.solutions.sort();
+    let Ok(mut game) = args_to_game(args) else {
+        return;
+    };
 
-        if get_yn_input("Anzahl der gefundenen Lösungen anzeigen (j/n)? ").is_err() {
-            return;
-        }
+    game.print_game();
+    let num = game.num;
+    let dices = game.dices.clone();
+    let mut fgame = fastgame::Game::new();
 
-        game.print_solution_amount();
+    let mut solutions = game.solve_for(num, &dices);
+    solutions.sort();
+    println!("game: {} Lösungen", solutions.len());
 
-        if game.solutions.len() == 0 {
-            return;
-        }
-
-        if get_yn_input("Lösungen anzeigen (j/n)? ").is_err() {
-            return;
-        }
+    let solutions = fgame.solve_for(num, &dices);
+    println!("fgame: {} Lösungen", solutions.len());
 
+    if get_yn_input("Lösungen anzeigen (j/n)? ").is_ok() {
+        println!("game");
         game.print_solutions();
+        println!("fgame");
+        fgame.print_solutions();
     }
 
-    let args = std::env::args().collect::<Vec<String>>();
-    let cube_count = match args[1].parse::<u8>() {
-        Ok(3) => 3,
-        Ok(4) => 4,
-        _ => {
-            eprintln!("Bitte gib entweder 3 oder 4 Würfel an.");
-            return;
-        }
-    };
-
-    let mut fgame = fastgame::Game::new(match cube_count {
-        3 => fastgame::DiceAmount::Three,
-        4 => fastgame::DiceAmount::Four,
-        _ => unreachable!(),
-    });
-    let num = args[2]
-        .parse::<u32>()
-        .expect("Argument <Nummer> muss eine Zahl sein");
-    let mut dices = [0; 4];
-    dices[0] = args[3]
-        .parse::<u8>()
-        .expect("Argument <Würfel1> muss eine Zahl sein");
-
-    dices[1] = args[4]
-        .parse::<u8>()
-        .expect("Argument <Würfel2> muss eine Zahl sein");
-
-    dices[2] = args[5]
-        .parse::<u8>()
-        .expect("Argument <Würfel3> muss eine Zahl sein");
-
-    if args.len() > 6 {
-        dices[3] = args[6]
-            .parse::<u8>()
-            .expect("Argument <Würfel4> muss eine Zahl sein");
+    let range = 0..10u64.pow(dices.len() as u32 - 1);
+    let mismatches = cross_check(&mut game, &mut fgame, range.clone(), &dices);
+    if mismatches.is_empty() {
+        println!("Beide Löser erreichen dieselben Zahlen in {:?}", range);
     } else {
-        dices[3] = 1; // Default value for the fourth die if not provided
+        println!("Unterschiedlich erreichbare Zahlen: {:?}", mismatches);
     }
-    fgame.solve(num, dices);
-
-    println!("fgame");
-    fgame.print_solutions();
 }
 
 fn args_to_game(args: Vec<String>) -> Result<Game> {
-    return match args.len() {
-        1 => {
-            return Err(anyhow!("Bitte gib eine Zahl an, die erreicht werden soll."));
-        }
-        2 => {
-            let amount_cubes = match args[1].parse::<u8>().unwrap() {
-                3 => DiceAmount::Three,
-                4 => DiceAmount::Four,
-                _ => {
-                    return Err(anyhow!("Nur 3 oder 4 Würfel sind erlaubt."));
-                }
-            };
-            Ok(Game::new(amount_cubes))
+    if args.len() == 1 {
+        return Err(anyhow!("Bitte gib eine Zahl an, die erreicht werden soll."));
+    }
+
+    let amount_cubes = match args[1].parse::<usize>() {
+        Ok(n) if n >= 2 => n,
+        _ => {
+            return Err(anyhow!("Es sind mindestens 2 Würfel nötig."));
         }
-        3 => {
-            let amount_cubes = match args[1].parse::<u8>().unwrap() {
-                3 => DiceAmount::Three,
-                4 => DiceAmount::Four,
-                _ => {
-                    return Err(anyhow!("Nur 3 oder 4 Würfel sind erlaubt."));
-                }
-            };
+    };
+
+    match args.len() - 2 {
+        0 => Ok(Game::new(amount_cubes)),
+        1 => {
             let num = args[2]
                 .parse::<u64>()
                 .expect("Argument <Nummer> muss eine Zahl sein");
 
             Ok(Game::of_number(amount_cubes, num))
         }
-
-        6 | 7 => {
-            let amount_cubes = match args[1].parse::<u8>().unwrap() {
-                3 => DiceAmount::Three,
-                4 => DiceAmount::Four,
-                _ => {
-                    return Err(anyhow!("Nur 3 oder 4 Würfel sind erlaubt."));
-                }
-            };
+        n if n == amount_cubes + 1 => {
             let num = args[2]
                 .parse::<u64>()
                 .expect("Argument <Nummer> muss eine Zahl sein");
 
-            let mut dices = [0; 4];
-            dices[0] = args[3]
-                .parse::<u64>()
-                .expect("Argument <Würfel1> muss eine Zahl sein");
-
-            dices[1] = args[4]
-                .parse::<u64>()
-                .expect("Argument <Würfel2> muss eine Zahl sein");
-
-            dices[2] = args[5]
-                .parse::<u64>()
-                .expect("Argument <Würfel3> muss eine Zahl sein");
-
-            if args.len() > 6 {
-                dices[3] = args[6]
-                    .parse::<u64>()
-                    .expect("Argument <Würfel4> muss eine Zahl sein");
-            }
-
-            Ok(Game::of(amount_cubes, num, dices))
+            let dices = args[3..]
+                .iter()
+                .enumerate()
+                .map(|(i, arg)| {
+                    arg.parse::<u64>().unwrap_or_else(|_| {
+                        panic!("Argument <Würfel{}> muss eine Zahl sein", i + 1)
+                    })
+                })
+                .collect();
+
+            Ok(Game::of(num, dices))
         }
         _ => Err(anyhow!(help())),
-    };
+    }
 }
 
 fn help() -> String {
     let mut help = String::new();
     help.push_str("Aufruf: ");
-    help.push_str("./nums <Nummer> <Würfel1> <Würfel2> <Würfel3> <Würfel4>\n");
+    help.push_str("./nums <Anzahl> <Nummer> <Würfel1> ... <WürfelN>\n");
     help.push_str("Möglichkeiten:\n");
-    help.push_str(" - 0 Argumente: Zufällige Zahl und Würfel\n");
-    help.push_str(" - 1 Argument: Vorgegebene Zahl und zufällige Würfel\n");
-    help.push_str(" - 5 Argumente: Vorgegebene Zahl und Würfel\n");
+    help.push_str(" - 1 Argument: Zufällige Zahl und <Anzahl> Würfel\n");
+    help.push_str(" - 2 Argumente: Vorgegebene Zahl und zufällige Würfel\n");
+    help.push_str(" - 2 + <Anzahl> Argumente: Vorgegebene Zahl und Würfel\n");
     help
 }
 
@@ -160,7 +102,7 @@ fn get_yn_input(question: &str) -> Result<()> {
 
         loop {
             let mut character = String::new();
-            if let Ok(_) = std::io::stdin().read_line(&mut character) {
+            if std::io::stdin().read_line(&mut character).is_ok() {
                 if character.len() != 2 {
                     continue 'outer;
                 }
@@ -0,0 +1,377 @@
+use std::{
+    cmp::Ordering,
+    collections::HashSet,
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use anyhow::{anyhow, Result};
+
+use crate::{
+    difficulty::{Classic, DifficultyModel},
+    operator::Operator,
+    rules::Rules,
+    value::{Rational, Value},
+};
+
+/// Expression combining the dice.
+///
+/// Two calculations are equal if they only differ in the order of the
+/// operands of a commutative operator or in which of two dice with the same
+/// value is used. `Hash` and `Ord` agree with this, calculations are ordered
+/// by their score first.
+#[derive(Debug, Clone, Eq)]
+pub enum Calculation {
+    Binary(Operator, Box<Calculation>, Box<Calculation>),
+    Unary(Operator, Box<Calculation>),
+    Cube(usize, u64),
+}
+
+impl Calculation {
+    pub fn binary(op: Operator, a: Calculation, b: Calculation) -> Self {
+        Calculation::Binary(op, Box::new(a), Box::new(b))
+    }
+
+    pub fn unary(op: Operator, a: Calculation) -> Self {
+        Calculation::Unary(op, Box::new(a))
+    }
+
+    /// Difficulty of the calculation in the [`Classic`] model.
+    pub fn score(&self) -> u32 {
+        Classic.score(self)
+    }
+
+    /// The value of the calculation, if every step is defined and
+    /// representable in `V`, e.g. no step overflows and every division is
+    /// exact for integers.
+    pub fn evaluate<V: Value>(&self) -> Option<V> {
+        match self {
+            Calculation::Binary(op, a, b) => op.apply(a.evaluate()?, b.evaluate()?),
+            Calculation::Unary(op, a) => op.apply_unary(a.evaluate()?),
+            Calculation::Cube(_, v) => V::from_int(*v),
+        }
+    }
+
+    /// Amount of dice used by the two parts of the final step, the smaller
+    /// one first. Unary operators on top are looked through.
+    pub fn split(&self) -> (usize, usize) {
+        match self {
+            Calculation::Binary(_, a, b) => {
+                let (a, b) = (a.dice_count(), b.dice_count());
+                (a.min(b), a.max(b))
+            }
+            Calculation::Unary(_, a) => a.split(),
+            Calculation::Cube(..) => (0, 1),
+        }
+    }
+
+    pub fn dice_count(&self) -> usize {
+        match self {
+            Calculation::Binary(_, a, b) => a.dice_count() + b.dice_count(),
+            Calculation::Unary(_, a) => a.dice_count(),
+            Calculation::Cube(..) => 1,
+        }
+    }
+
+    /// The value of the calculation as a non-negative integer, computed step
+    /// by step like the solvers do: every intermediate result has to be
+    /// representable and allowed by `rules`, so a negative one is an error
+    /// unless `rules.negative` is set.
+    pub fn value(&self, rules: &Rules) -> Result<u64> {
+        let value = match (rules.rational, rules.negative) {
+            (true, _) => self.evaluate_with::<Rational>(rules)?.to_int(),
+            (false, true) => self.evaluate_with::<i64>(rules)?.to_int(),
+            (false, false) => self.evaluate_with::<u64>(rules)?.to_int(),
+        };
+        value.ok_or_else(|| anyhow!("{} ist keine natürliche Zahl", self))
+    }
+
+    fn evaluate_with<V: Value>(&self, rules: &Rules) -> Result<V> {
+        let value = match self {
+            Calculation::Binary(op, a, b) => {
+                op.apply(a.evaluate_with(rules)?, b.evaluate_with(rules)?)
+            }
+            Calculation::Unary(op, a) => op.apply_unary(a.evaluate_with(rules)?),
+            Calculation::Cube(_, v) => V::from_int(*v),
+        };
+        match value {
+            Some(value) if rules.allows(value) => Ok(value),
+            Some(_) => Err(anyhow!(
+                "{} ist negativ, negative Zwischenergebnisse sind nicht erlaubt",
+                self
+            )),
+            None => Err(anyhow!("{} ist nicht berechenbar", self)),
+        }
+    }
+
+    /// Checks that the calculation is a solution of reaching `num` with
+    /// `dices`: it only uses operators of `rules`, uses every die exactly
+    /// once with one of the allowed multipliers and evaluates to `num` with
+    /// the intermediate results `rules` allow.
+    pub fn verify(&self, num: u64, dices: &[u64], rules: &Rules) -> Result<()> {
+        let mut used = vec![0; dices.len()];
+        self.verify_parts(dices, rules, &mut used)?;
+
+        if let Some(i) = used.iter().position(|&count| count != 1) {
+            return Err(anyhow!("Würfel{} wird {} mal benutzt", i + 1, used[i]));
+        }
+
+        match self.value(rules)? {
+            value if value == num => Ok(()),
+            value => Err(anyhow!("Ergebnis ist {} statt {}", value, num)),
+        }
+    }
+
+    fn verify_parts(&self, dices: &[u64], rules: &Rules, used: &mut [usize]) -> Result<()> {
+        match self {
+            Calculation::Binary(op, a, b) => {
+                if !rules.operators.contains(op) {
+                    return Err(anyhow!("Operator {} ist nicht erlaubt", op));
+                }
+                a.verify_parts(dices, rules, used)?;
+                b.verify_parts(dices, rules, used)
+            }
+            Calculation::Unary(op, a) => {
+                if !rules.operators.contains(op) {
+                    return Err(anyhow!("Operator {} ist nicht erlaubt", op));
+                }
+                a.verify_parts(dices, rules, used)
+            }
+            Calculation::Cube(i, v) => {
+                let Some(&die) = dices.get(*i) else {
+                    return Err(anyhow!("Würfel{} gibt es nicht", i + 1));
+                };
+                if !rules.die_values(die).any(|value| value == *v) {
+                    return Err(anyhow!("{} ist kein erlaubtes Vielfaches von {}", v, die));
+                }
+                used[*i] += 1;
+                Ok(())
+            }
+        }
+    }
+
+    /// Canonical form of the calculation, equal for algebraically equivalent
+    /// reorderings like `((a + b) + c)` and `(a + (c + b))`.
+    ///
+    /// Chains of `+` and `-` are flattened into the added and the subtracted
+    /// terms, chains of `*` and `/` into the multiplied and the divided
+    /// factors. Both are sorted and rebuilt left to right, so
+    /// `(a - (b - c))` becomes `((a + c) - b)`.
+    pub fn normalize(&self) -> Calculation {
+        match self {
+            Calculation::Binary(Operator::Add | Operator::Sub, ..) => {
+                let (mut added, mut subtracted) = (Vec::new(), Vec::new());
+                self.flatten(
+                    Operator::Add,
+                    Operator::Sub,
+                    false,
+                    &mut added,
+                    &mut subtracted,
+                );
+
+                Self::rebuild(Operator::Add, Operator::Sub, added, subtracted)
+            }
+            Calculation::Binary(Operator::Mul | Operator::Div, ..) => {
+                let (mut multiplied, mut divided) = (Vec::new(), Vec::new());
+                self.flatten(
+                    Operator::Mul,
+                    Operator::Div,
+                    false,
+                    &mut multiplied,
+                    &mut divided,
+                );
+
+                Self::rebuild(Operator::Mul, Operator::Div, multiplied, divided)
+            }
+            Calculation::Binary(op, a, b) => Calculation::binary(*op, a.normalize(), b.normalize()),
+            Calculation::Unary(op, a) => Calculation::unary(*op, a.normalize()),
+            Calculation::Cube(i, v) => Calculation::Cube(*i, *v),
+        }
+    }
+
+    /// Collects the normalized operands of a chain of `op` and its inverse.
+    /// `inverted` tells whether the calculation itself is on the inverted
+    /// side, like `b` in `(a - b)`.
+    fn flatten(
+        &self,
+        op: Operator,
+        inverse: Operator,
+        inverted: bool,
+        terms: &mut Vec<Calculation>,
+        inverted_terms: &mut Vec<Calculation>,
+    ) {
+        match self {
+            Calculation::Binary(o, a, b) if *o == op || *o == inverse => {
+                a.flatten(op, inverse, inverted, terms, inverted_terms);
+                b.flatten(
+                    op,
+                    inverse,
+                    inverted ^ (*o == inverse),
+                    terms,
+                    inverted_terms,
+                );
+            }
+            _ if inverted => inverted_terms.push(self.normalize()),
+            _ => terms.push(self.normalize()),
+        }
+    }
+
+    fn rebuild(
+        op: Operator,
+        inverse: Operator,
+        mut terms: Vec<Calculation>,
+        mut inverted_terms: Vec<Calculation>,
+    ) -> Calculation {
+        terms.sort_by(Self::structural_cmp);
+        inverted_terms.sort_by(Self::structural_cmp);
+
+        // the first operand of a chain is never inverted, so there is at
+        // least one term
+        let mut terms = terms.into_iter();
+        let first = terms.next().expect("chain without a leading term");
+        let calc = terms.fold(first, |acc, term| Calculation::binary(op, acc, term));
+        inverted_terms
+            .into_iter()
+            .fold(calc, |acc, term| Calculation::binary(inverse, acc, term))
+    }
+
+    /// Order of the structure alone, the operands in the canonical form are
+    /// sorted by it: cubes first with larger values in front, then unary and
+    /// then binary calculations. Consistent with `==`, so the operands of
+    /// commutative operators are compared in sorted order.
+    fn structural_cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Calculation::Cube(_, a), Calculation::Cube(_, b)) => b.cmp(a),
+            (Calculation::Cube(..), _) => Ordering::Less,
+            (_, Calculation::Cube(..)) => Ordering::Greater,
+            (Calculation::Unary(op1, a), Calculation::Unary(op2, b)) => {
+                op1.cmp(op2).then_with(|| a.structural_cmp(b))
+            }
+            (Calculation::Unary(..), _) => Ordering::Less,
+            (_, Calculation::Unary(..)) => Ordering::Greater,
+            (Calculation::Binary(op1, a, b), Calculation::Binary(op2, c, d)) => {
+                op1.cmp(op2).then_with(|| {
+                    let ((a, b), (c, d)) = if op1.is_commutative() {
+                        (Self::sorted(a, b), Self::sorted(c, d))
+                    } else {
+                        ((&**a, &**b), (&**c, &**d))
+                    };
+                    a.structural_cmp(c).then_with(|| b.structural_cmp(d))
+                })
+            }
+        }
+    }
+
+    fn sorted<'a>(a: &'a Self, b: &'a Self) -> (&'a Self, &'a Self) {
+        if a.structural_cmp(b) == Ordering::Greater {
+            (b, a)
+        } else {
+            (a, b)
+        }
+    }
+}
+
+/// Keeps the first of every group of `solutions` with the same canonical
+/// form, see [`Calculation::normalize`].
+pub fn canonical_solutions(solutions: impl IntoIterator<Item = Calculation>) -> Vec<Calculation> {
+    let mut seen = HashSet::new();
+    let mut kept = Vec::new();
+    for solution in solutions {
+        if seen.insert(solution.normalize()) {
+            kept.push(solution);
+        }
+    }
+
+    kept
+}
+
+impl PartialOrd for Calculation {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Calculation {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score()
+            .cmp(&other.score())
+            .then_with(|| self.structural_cmp(other))
+    }
+}
+
+impl PartialEq for Calculation {
+    fn eq(&self, other: &Self) -> bool {
+        match self {
+            Calculation::Cube(_, v) => {
+                if let Calculation::Cube(_, w) = other {
+                    return v == w;
+                }
+            }
+
+            Calculation::Binary(op, a, b) => {
+                if let Calculation::Binary(other_op, c, d) = other {
+                    if op != other_op {
+                        return false;
+                    }
+                    if op.is_commutative() {
+                        return (*a == *c && *b == *d) || (*a == *d && *b == *c);
+                    }
+                    return *a == *c && *b == *d;
+                }
+            }
+
+            Calculation::Unary(op, a) => {
+                if let Calculation::Unary(other_op, b) = other {
+                    return op == other_op && *a == *b;
+                }
+            }
+        }
+        false
+    }
+}
+
+impl Hash for Calculation {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Calculation::Binary(op, a, b) => {
+                state.write_u8(0);
+                op.hash(state);
+                if op.is_commutative() {
+                    // order independent, like the equality
+                    let (a, b) = (hash_of(a), hash_of(b));
+                    state.write_u64(a.min(b));
+                    state.write_u64(a.max(b));
+                } else {
+                    a.hash(state);
+                    b.hash(state);
+                }
+            }
+            Calculation::Unary(op, a) => {
+                state.write_u8(1);
+                op.hash(state);
+                a.hash(state);
+            }
+            // the index of the die is not part of the equality
+            Calculation::Cube(_, v) => {
+                state.write_u8(2);
+                v.hash(state);
+            }
+        }
+    }
+}
+
+fn hash_of(calc: &Calculation) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    calc.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl std::fmt::Display for Calculation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Calculation::Binary(op, a, b) => write!(f, "({} {} {})", a, op.symbol(), b),
+            Calculation::Unary(Operator::Factorial, a) => write!(f, "{}!", a),
+            Calculation::Unary(op, a) => write!(f, "{}{}", op.symbol(), a),
+            Calculation::Cube(_, c) => write!(f, "{}", c),
+        }
+    }
+}
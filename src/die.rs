@@ -0,0 +1,133 @@
+use anyhow::{anyhow, Error, Result};
+use rand::Rng;
+
+/// The faces a die can show.
+///
+/// The classic game is played with d6. Faces are kept sorted and free of
+/// duplicates.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Die {
+    faces: Vec<u64>,
+}
+
+impl Default for Die {
+    fn default() -> Self {
+        Self::d6()
+    }
+}
+
+impl Die {
+    /// A die with the given faces, which must not be empty.
+    pub fn of(faces: Vec<u64>) -> Result<Self> {
+        let mut faces = faces;
+        faces.sort();
+        faces.dedup();
+
+        if faces.is_empty() {
+            return Err(anyhow!("Ein Würfel braucht mindestens eine Seite."));
+        }
+
+        Ok(Self { faces })
+    }
+
+    /// A die with the faces `1..=sides`.
+    pub fn sides(sides: u64) -> Self {
+        Self {
+            faces: (1..=sides.max(1)).collect(),
+        }
+    }
+
+    pub fn d4() -> Self {
+        Self::sides(4)
+    }
+
+    pub fn d6() -> Self {
+        Self::sides(6)
+    }
+
+    pub fn d8() -> Self {
+        Self::sides(8)
+    }
+
+    /// A d10 with the faces `0..=9`.
+    pub fn d10() -> Self {
+        Self {
+            faces: (0..=9).collect(),
+        }
+    }
+
+    pub fn d12() -> Self {
+        Self::sides(12)
+    }
+
+    pub fn d20() -> Self {
+        Self::sides(20)
+    }
+
+    pub fn faces(&self) -> &[u64] {
+        &self.faces
+    }
+
+    pub fn roll(&self, rng: &mut impl Rng) -> u64 {
+        self.faces[rng.random_range(0..self.faces.len())]
+    }
+
+    pub fn has_face(&self, value: u64) -> bool {
+        self.faces.binary_search(&value).is_ok()
+    }
+
+    /// Checks that every value in `dices` is a face of the die.
+    pub fn check(&self, dices: &[u64]) -> Result<()> {
+        match dices.iter().position(|d| !self.has_face(*d)) {
+            Some(i) => Err(anyhow!(
+                "Würfel{} zeigt {}, das ist keine Seite von {}",
+                i + 1,
+                dices[i],
+                self
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+impl std::str::FromStr for Die {
+    type Err = Error;
+
+    /// Parses `d4`, `d6`, `d8`, `d10`, `d12`, `d20` or a comma separated
+    /// list of faces like `1,2,3,5,8`, also in brackets as it is displayed.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "d4" => Ok(Self::d4()),
+            "d6" => Ok(Self::d6()),
+            "d8" => Ok(Self::d8()),
+            "d10" => Ok(Self::d10()),
+            "d12" => Ok(Self::d12()),
+            "d20" => Ok(Self::d20()),
+            _ => {
+                let list = s.strip_prefix('[').and_then(|s| s.strip_suffix(']'));
+                let faces = list
+                    .unwrap_or(s)
+                    .split(',')
+                    .map(|face| face.trim().parse::<u64>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| anyhow!("Unbekannter Würfel: {}", s))?;
+
+                Self::of(faces)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Die {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if *self == Self::d10() {
+            return write!(f, "d10");
+        }
+        if *self == Self::sides(self.faces.len() as u64) {
+            return write!(f, "d{}", self.faces.len());
+        }
+
+        let faces = self.faces.iter().map(|f| f.to_string()).collect::<Vec<_>>();
+        write!(f, "[{}]", faces.join(","))
+    }
+}
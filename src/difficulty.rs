@@ -0,0 +1,343 @@
+use std::ops::Range;
+
+use anyhow::{anyhow, Error};
+
+use crate::{
+    calculation::{canonical_solutions, Calculation},
+    operator::Operator,
+    value::{Rational, Value},
+};
+
+/// Rates how hard a calculation is to find and to compute, higher is
+/// harder.
+pub trait DifficultyModel {
+    fn score(&self, calc: &Calculation) -> u32;
+}
+
+/// Fixed weights per operator and die, see [`Operator::weight`]. This is
+/// what [`Calculation::score`] and the order of calculations use.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Classic;
+
+impl DifficultyModel for Classic {
+    fn score(&self, calc: &Calculation) -> u32 {
+        match calc {
+            Calculation::Binary(op, a, b) => op.weight() + self.score(a) + self.score(b),
+            Calculation::Unary(op, a) => op.weight() + self.score(a),
+            Calculation::Cube(_, v) => 10 + 2 * v.checked_ilog10().unwrap_or(0),
+        }
+    }
+}
+
+/// Effort of computing the calculation in the head: carries and borrows,
+/// the amount of significant digits that are multiplied or divided, large
+/// intermediate results and divisions or fractions that do not come out
+/// round.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MentalArithmetic;
+
+impl DifficultyModel for MentalArithmetic {
+    fn score(&self, calc: &Calculation) -> u32 {
+        self.score_with_value(calc).0
+    }
+}
+
+impl MentalArithmetic {
+    fn score_with_value(&self, calc: &Calculation) -> (u32, Option<Rational>) {
+        match calc {
+            Calculation::Binary(op, a, b) => {
+                let (score_a, a) = self.score_with_value(a);
+                let (score_b, b) = self.score_with_value(b);
+                let value = a.zip(b).and_then(|(a, b)| op.apply(a, b));
+                let step = match (a.and_then(Value::to_int), b.and_then(Value::to_int)) {
+                    (Some(a), Some(b)) => Self::step(*op, a, b),
+                    // negative or fractional operands
+                    _ => op.weight() + 20,
+                };
+
+                (score_a + score_b + step + Self::size(value), value)
+            }
+            Calculation::Unary(op, a) => {
+                let (score, a) = self.score_with_value(a);
+                let value = a.and_then(|a| op.apply_unary(a));
+
+                (score + op.weight() + Self::size(value), value)
+            }
+            Calculation::Cube(_, v) => (5 + significant_digits(*v), Rational::from_int(*v)),
+        }
+    }
+
+    /// Effort of one step with non-negative integer operands.
+    fn step(op: Operator, a: u64, b: u64) -> u32 {
+        match op {
+            Operator::Add => 5 + 6 * carries(a, b),
+            Operator::Sub if a >= b => 6 + 6 * carries(a - b, b),
+            Operator::Mul => 8 + 4 * significant_digits(a) * significant_digits(b),
+            Operator::Div if b != 0 && a.is_multiple_of(b) => {
+                let quotient = a / b;
+                // 600 / 3 is easier than 108 / 3
+                let round = if significant_digits(quotient) > 1 {
+                    15
+                } else {
+                    0
+                };
+                10 + 4 * significant_digits(a) * significant_digits(b) + round
+            }
+            // the result is no integer, or the division is not defined
+            Operator::Div => op.weight() + 20,
+            // the result is negative and has to be kept in mind with its sign
+            Operator::Sub => 16 + 6 * carries(b - a, a),
+            // a multiplication for every step of the exponent
+            Operator::Pow => 8 + 4 * significant_digits(a) * b.min(10) as u32,
+            // a division that keeps its remainder
+            Operator::Mod => 12 + 4 * significant_digits(a) * significant_digits(b),
+            // the digits are only written next to each other
+            Operator::Concat => 5,
+            // unary operators are not a step with two operands
+            Operator::Sqrt | Operator::Factorial => op.weight(),
+        }
+    }
+
+    /// Effort of keeping an intermediate result in mind.
+    fn size(value: Option<Rational>) -> u32 {
+        match value {
+            Some(value) if value.denominator() == 1 => {
+                2 * significant_digits(value.numerator().unsigned_abs())
+            }
+            Some(value) => {
+                20 + 2 * significant_digits(value.numerator().unsigned_abs())
+                    + 2 * significant_digits(value.denominator().unsigned_abs())
+            }
+            None => 0,
+        }
+    }
+}
+
+/// Digits of `n` without its trailing zeros, `3400` has two.
+fn significant_digits(mut n: u64) -> u32 {
+    if n == 0 {
+        return 1;
+    }
+    while n.is_multiple_of(10) {
+        n /= 10;
+    }
+    n.ilog10() + 1
+}
+
+/// Carries when adding `a` and `b` digit by digit. Subtracting `b` from `a`
+/// borrows as often as adding `b` to `a - b` carries.
+fn carries(mut a: u64, mut b: u64) -> u32 {
+    let (mut carry, mut carries) = (0, 0);
+    while a > 0 || b > 0 {
+        carry = (a % 10 + b % 10 + carry) / 10;
+        carries += carry as u32;
+        a /= 10;
+        b /= 10;
+    }
+    carries
+}
+
+/// The difficulty models to choose from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Difficulty {
+    #[default]
+    Classic,
+    MentalArithmetic,
+}
+
+impl DifficultyModel for Difficulty {
+    fn score(&self, calc: &Calculation) -> u32 {
+        match self {
+            Difficulty::Classic => Classic.score(calc),
+            Difficulty::MentalArithmetic => MentalArithmetic.score(calc),
+        }
+    }
+}
+
+impl std::str::FromStr for Difficulty {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "classic" => Ok(Difficulty::Classic),
+            "mental" => Ok(Difficulty::MentalArithmetic),
+            _ => Err(anyhow!("Unbekanntes Schwierigkeitsmodell: {}", s)),
+        }
+    }
+}
+
+/// Difficulty of a whole game, rated from its solutions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rating {
+    /// Amount of solutions that are not just reorderings of each other.
+    pub solutions: usize,
+
+    /// Score of the easiest solution.
+    pub min_score: u32,
+
+    /// Difference between the scores of the hardest and the easiest solution.
+    pub spread: u32,
+
+    /// Whether some solution combines two parts of at least two dice each in
+    /// its final step, like `(a + b) * (c + d)`, instead of only one die
+    /// with the rest, like `((a + b) * c) - d`.
+    pub balanced: bool,
+
+    /// The overall rating, higher is harder. The score of the easiest
+    /// solution is scaled to a game with four dice, the bonuses are not.
+    pub score: u32,
+
+    /// The band the rating falls into.
+    pub level: Level,
+}
+
+impl Rating {
+    /// Rates a game with `solutions` using `model`, `None` if there are no
+    /// solutions.
+    pub fn of<'a>(
+        solutions: impl IntoIterator<Item = &'a Calculation>,
+        model: &impl DifficultyModel,
+    ) -> Option<Self> {
+        let canonical = canonical_solutions(solutions.into_iter().cloned());
+        let scores = canonical.iter().map(|solution| model.score(solution));
+        let min_score = scores.clone().min()?;
+        let spread = scores.max()? - min_score;
+        let balanced = canonical.iter().any(|solution| solution.split().0 >= 2);
+
+        // few solutions are hard to find
+        let scarcity = match canonical.len() {
+            1 => 40,
+            2..=3 => 25,
+            4..=9 => 10,
+            _ => 0,
+        };
+        // with many dice, a last step with a single die is harder to see
+        let unbalanced = if !balanced && canonical[0].dice_count() >= 4 {
+            15
+        } else {
+            0
+        };
+        // if every solution is about as hard as the easiest, there is no
+        // simpler way around it
+        let uniform = if spread < 20 { 5 } else { 0 };
+
+        let score = scaled(min_score, canonical[0].dice_count()) + scarcity + unbalanced + uniform;
+        Some(Self {
+            solutions: canonical.len(),
+            min_score,
+            spread,
+            balanced,
+            score,
+            level: Level::of(score),
+        })
+    }
+}
+
+impl std::fmt::Display for Rating {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}, {} ({} verschiedene Lösungen, einfachste {}, Spanne {}, {})",
+            self.level,
+            self.score,
+            self.solutions,
+            self.min_score,
+            self.spread,
+            if self.balanced {
+                "Aufteilung in zwei Paare möglich"
+            } else {
+                "nur ein einzelner Würfel im letzten Schritt"
+            }
+        )
+    }
+}
+
+/// Bands of game ratings.
+///
+/// The bands are calibrated for the [`Classic`] model on random games with
+/// four dice, so that about a quarter of the solvable ones falls into each
+/// of them. With three dice they are far from even: less than one in a
+/// hundred solvable games is an expert game, so [`Game::generate`] mostly
+/// misses that band and returns the closest game it found instead.
+///
+/// [`Game::generate`]: crate::game::Game::generate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Easy,
+    Medium,
+    Hard,
+    Expert,
+}
+
+impl Level {
+    /// Name of the level on the command line, see the `FromStr` impl.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Level::Easy => "easy",
+            Level::Medium => "medium",
+            Level::Hard => "hard",
+            Level::Expert => "expert",
+        }
+    }
+
+    /// Ratings of the band, see [`Rating::score`].
+    fn range(&self) -> Range<u32> {
+        match self {
+            Level::Easy => 0..135,
+            Level::Medium => 135..157,
+            Level::Hard => 157..171,
+            Level::Expert => 171..u32::MAX,
+        }
+    }
+
+    /// The band of a rating `score`, see [`Rating::score`].
+    pub fn of(score: u32) -> Self {
+        [Level::Easy, Level::Medium, Level::Hard, Level::Expert]
+            .into_iter()
+            .find(|level| level.range().contains(&score))
+            .unwrap_or(Level::Expert)
+    }
+
+    /// How far the rating `score` is outside of the band, 0 if it falls
+    /// into it.
+    pub fn distance(&self, score: u32) -> u32 {
+        let range = self.range();
+        if score < range.start {
+            range.start - score
+        } else {
+            (score + 1).saturating_sub(range.end)
+        }
+    }
+}
+
+/// Scores of solutions grow about linearly with the amount of dice, this
+/// scales them to a game with four dice.
+fn scaled(score: u32, num_dices: usize) -> u32 {
+    (score as usize * 4 / num_dices.max(1)) as u32
+}
+
+impl std::str::FromStr for Level {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "easy" => Ok(Level::Easy),
+            "medium" => Ok(Level::Medium),
+            "hard" => Ok(Level::Hard),
+            "expert" => Ok(Level::Expert),
+            _ => Err(anyhow!("Unbekannte Schwierigkeitsstufe: {}", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Level::Easy => "leicht",
+            Level::Medium => "mittel",
+            Level::Hard => "schwer",
+            Level::Expert => "Experte",
+        };
+        write!(f, "{}", name)
+    }
+}
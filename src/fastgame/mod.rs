@@ -1,594 +1,311 @@
-use crate::{fastgame::calculation::Calculation, output::Output};
-use std::collections::{HashMap, HashSet};
+use crate::{
+    arena::{Arena, Node, NodeId},
+    calculation::{canonical_solutions, Calculation},
+    difficulty::{Difficulty, DifficultyModel, Rating},
+    operator::{combinations, unary_combinations},
+    output::Output,
+    reach,
+    results::{apply_unary, binary, check_for_closest},
+    rules::Rules,
+    solver::{final_pairs, CancelToken, Closest, Solver, Stop},
+    subsets::splits,
+    value::{Rational, Value},
+};
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct Game {
+    pub num: u64,
+    pub dices: Vec<u8>,
+    pub rules: Rules,
+    pub solutions: HashSet<Calculation>,
 
-mod calculation;
+    /// Keep solutions that only differ by reordering, like `((a + b) + c)`
+    /// and `(a + (b + c))`, instead of only the first of them.
+    pub keep_equivalent: bool,
 
-const MAX_DICES: usize = 4;
-type DiceValue = u32;
+    /// Amount of solutions before equivalent ones were collapsed.
+    pub raw_solution_count: usize,
 
-#[derive(Debug, Clone)]
-pub struct Game {
-    pub num_dices: DiceAmount,
-    pub num: DiceValue,
-    pub dices: [u8; MAX_DICES],
-    pub solutions: HashSet<Calculation>,
+    /// The results nearest to `num`, if it can not be reached exactly.
+    pub closest: Closest,
 
-    pub result_map_pool: Vec<HashMap<DiceValue, Calculation>>,
-    pub dp: HashMap<UsedCubes, HashMap<DiceValue, Calculation>>,
-}
+    /// Rates the solutions for choosing the easiest and the hardest.
+    pub difficulty: Difficulty,
+
+    /// Stops the search early, see [`CancelToken`].
+    pub cancel: CancelToken,
 
-#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
-pub enum DiceAmount {
-    Four,
-    Three,
+    /// Whether the last search was stopped by `cancel` before it was done,
+    /// so `solutions` or the reachable values may be missing some.
+    pub incomplete: bool,
+
+    integer_dp: Dp<u64>,
+    signed_dp: Dp<i64>,
+    rational_dp: Dp<Rational>,
 }
 
-#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
-pub enum UsedCubes {
-    OneCube(DiceValue),
-    TwoCubes(DiceValue, DiceValue),
-    ThreeCubes(DiceValue, DiceValue, DiceValue),
+/// Result maps indexed by the bitmask of the used dice, together with
+/// cleared maps that are reused by the next solve. The calculations of the
+/// results are nodes of the arena.
+#[derive(Debug, Clone)]
+struct Dp<V> {
+    maps: Vec<HashMap<V, NodeId>>,
+    pool: Vec<HashMap<V, NodeId>>,
+    arena: Arena,
 }
 
-impl Game {
-    pub fn new(num_dices: DiceAmount) -> Self {
+impl<V> Default for Dp<V> {
+    fn default() -> Self {
         Self {
-            num_dices,
-            num: 0,
-            dices: [0u8; MAX_DICES],
-            solutions: HashSet::new(),
-
-            result_map_pool: Vec::new(),
-            dp: HashMap::new(),
+            maps: Vec::new(),
+            pool: Vec::new(),
+            arena: Arena::new(),
         }
     }
+}
 
-    pub fn solve(&mut self, value: DiceValue, dices: [u8; MAX_DICES]) {
-        self.num = value;
-        self.dices = dices;
-        self.solutions.clear();
-        self.dp.clear();
-
-        match self.num_dices {
-            DiceAmount::Three => self.solve_three(),
-            DiceAmount::Four => self.solve_four(),
-        }
+impl Game {
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    pub fn solve_three_fast(
-        &mut self,
-        min: u16,
-        max: u16,
-        dices: [u8; MAX_DICES],
-        solutions: &mut Vec<u16>,
-    ) {
+    pub fn solve(&mut self, value: u64, dices: &[u8]) {
+        self.num = value;
+        self.dices.clear();
+        self.dices.extend_from_slice(dices);
         self.solutions.clear();
-        self.dp.clear();
-        let mut ds: [DiceValue; 3] = [0; 3];
-        ds[0] = dices[0] as DiceValue;
-        ds[1] = dices[1] as DiceValue;
-        ds[2] = dices[2] as DiceValue;
-
-        let mut checking = HashSet::new();
-        let mut found = HashSet::new();
-        for i in min..max {
-            checking.insert(i);
-        }
-
-        // single cubes
-        for (i, c) in ds.iter().enumerate() {
-            let mut map: HashMap<DiceValue, Calculation> = self
-                .result_map_pool
-                .pop()
-                .or_else(|| Some(HashMap::new()))
-                .unwrap();
-
-            map.insert(*c, Calculation::Cube(i, *c));
-            map.insert((*c) * 10, Calculation::Cube(i, (*c) * 10));
-            map.insert((*c) * 100, Calculation::Cube(i, (*c) * 100));
-            map.insert((*c) * 1000, Calculation::Cube(i, (*c) * 1000));
-
-            map.insert((*c) * 10000, Calculation::Cube(i, (*c) * 10000));
-            map.insert((*c) * 100000, Calculation::Cube(i, (*c) * 100000));
-            map.insert((*c) * 1000000, Calculation::Cube(i, (*c) * 1000000));
-            map.insert((*c) * 10000000, Calculation::Cube(i, (*c) * 10000000));
-
-            self.dp.insert(UsedCubes::OneCube(i as DiceValue), map);
-        }
-
-        // two cubes
-        let mut maps = Vec::new();
-
-        for (c1, map1) in self.dp.iter() {
-            for (c2, map2) in self.dp.iter() {
-                let UsedCubes::OneCube(c1) = c1 else { panic!() };
-                let UsedCubes::OneCube(c2) = c2 else { panic!() };
-
-                if c1 <= c2 {
-                    continue;
-                }
-
-                let mut map: HashMap<DiceValue, Calculation> = self
-                    .result_map_pool
-                    .pop()
-                    .or_else(|| Some(HashMap::new()))
-                    .unwrap();
-
-                calculate_result_map(map1, map2, &mut map);
-                maps.push((UsedCubes::TwoCubes(*c1, *c2), map));
-            }
-        }
-
-        self.dp.extend(maps);
-
-        // three cubes
-        for (cubes1, map1) in self.dp.iter() {
-            if let UsedCubes::OneCube(c1) = cubes1 {
-                for (cubes2, map2) in self.dp.iter() {
-                    if let UsedCubes::TwoCubes(c2, c3) = cubes2 {
-                        if c1 == c2 || c1 == c3 {
-                            continue;
-                        }
-
-                        checking.retain(|x| {
-                            if has_solution(map1, map2, *x as DiceValue) {
-                                let _ = found.insert(*x);
-                                false
-                            } else {
-                                true
-                            }
-                        });
-                    }
+        self.closest.clear();
+
+        let (dices, rules) = (&self.dices, &self.rules);
+        let (solutions, closest) = (&mut self.solutions, &mut self.closest);
+        let stop = Stop::new(&self.cancel);
+        match (rules.rational, rules.negative) {
+            (true, _) => self
+                .rational_dp
+                .solve(dices, rules, value, solutions, closest, &stop),
+            (false, true) => self
+                .signed_dp
+                .solve(dices, rules, value, solutions, closest, &stop),
+            (false, false) => self
+                .integer_dp
+                .solve(dices, rules, value, solutions, closest, &stop),
+        }
+        self.incomplete = stop.stopped();
+
+        if !self.solutions.is_empty() {
+            self.closest.clear();
+        }
+
+        self.raw_solution_count = self.solutions.len();
+        if !self.keep_equivalent {
+            let solutions = canonical_solutions(self.solutions.drain());
+            self.solutions.extend(solutions);
+            self.closest.keep_canonical();
+        }
+
+        #[cfg(debug_assertions)]
+        {
+            let dices = self.dices.iter().map(|&d| d.into()).collect::<Vec<_>>();
+            for solution in &self.solutions {
+                if let Err(e) = solution.verify(value, &dices, &self.rules) {
+                    panic!("Ungültige Lösung {}: {}", solution, e);
                 }
             }
         }
+    }
 
-        for (_, mut map1) in self.dp.drain() {
-            map1.clear();
-            self.result_map_pool.push(map1);
-        }
+    /// Collects every value in `min..max` that can be reached with `dices`
+    /// into `solutions`, in ascending order.
+    pub fn solve_fast(&mut self, min: u16, max: u16, dices: &[u8], solutions: &mut Vec<u16>) {
+        let found = self.reachable_in(min.into()..max.into(), dices);
 
-        let mut found = found.iter().collect::<Vec<_>>();
-        found.sort();
         solutions.clear();
-        solutions.extend(found.iter().map(|&&x| x as u16));
+        solutions.extend(found.into_iter().map(|x| x as u16));
     }
 
-    pub fn solve_four_fast(
-        &mut self,
-        min: u16,
-        max: u16,
-        dices: [u8; MAX_DICES],
-        solutions: &mut Vec<u16>,
-    ) {
+    fn reachable_in(&mut self, range: Range<u64>, dices: &[u8]) -> Vec<u64> {
+        self.dices.clear();
+        self.dices.extend_from_slice(dices);
         self.solutions.clear();
-        self.dp.clear();
-        let mut ds: [DiceValue; 4] = [0; 4];
-        ds[0] = dices[0] as DiceValue;
-        ds[1] = dices[1] as DiceValue;
-        ds[2] = dices[2] as DiceValue;
-        ds[3] = dices[3] as DiceValue;
-
-        let mut checking = HashSet::new();
-        let mut found = HashSet::new();
-        for i in min..max {
-            checking.insert(i);
-        }
-
-        let mut dp: HashMap<UsedCubes, HashMap<DiceValue, Calculation>> = HashMap::new();
-
-        // single cubes
-        for (i, c) in ds.iter().enumerate() {
-            let mut map: HashMap<DiceValue, Calculation> = HashMap::new();
-            map.insert(*c, Calculation::Cube(i, *c));
-            map.insert((*c) * 10, Calculation::Cube(i, (*c) * 10));
-            map.insert((*c) * 100, Calculation::Cube(i, (*c) * 100));
-            map.insert((*c) * 1000, Calculation::Cube(i, (*c) * 1000));
-
-            map.insert((*c) * 10000, Calculation::Cube(i, (*c) * 10000));
-            map.insert((*c) * 100000, Calculation::Cube(i, (*c) * 100000));
-            map.insert((*c) * 1000000, Calculation::Cube(i, (*c) * 1000000));
-            map.insert((*c) * 10000000, Calculation::Cube(i, (*c) * 10000000));
-
-            dp.insert(UsedCubes::OneCube(i as DiceValue), map);
-        }
 
-        // two cubes
-        let mut maps = Vec::new();
+        let dices = dices.iter().map(|&d| d.into()).collect::<Vec<u64>>();
+        let stop = Stop::new(&self.cancel);
+        let found = reach::coverage(&self.rules, range, &dices, &stop);
+        self.incomplete = stop.stopped();
 
-        for (c1, map1) in dp.iter() {
-            for (c2, map2) in dp.iter() {
-                let UsedCubes::OneCube(c1) = c1 else { panic!() };
-                let UsedCubes::OneCube(c2) = c2 else { panic!() };
+        found.iter().collect()
+    }
 
-                if c1 <= c2 {
-                    continue;
-                }
+    /// Difficulty of the game, `None` if it has no solution.
+    pub fn rating(&self) -> Option<Rating> {
+        Rating::of(&self.solutions, &self.difficulty)
+    }
 
-                let mut map: HashMap<DiceValue, Calculation> = self
-                    .result_map_pool
-                    .pop()
-                    .or_else(|| Some(HashMap::new()))
-                    .unwrap();
-                calculate_result_map(map1, map2, &mut map);
-                maps.push((UsedCubes::TwoCubes(*c1, *c2), map));
-            }
+    pub fn print_rating(&self) {
+        if let Some(rating) = self.rating() {
+            println!("Schwierigkeit des Spiels: {}\n", rating);
         }
+    }
 
-        while let Some((c, m)) = maps.pop() {
-            dp.insert(c, m);
-        }
+    pub fn print_game(&self) {
+        println!("{}", self);
+    }
 
-        // result of calculation of two cubes twice
-        for (cubes1, map1) in dp.iter() {
-            for (cubes2, map2) in dp.iter() {
-                if let UsedCubes::TwoCubes(c1, c2) = cubes1 {
-                    if let UsedCubes::TwoCubes(c3, c4) = cubes2 {
-                        if c1 == c3 || c1 == c4 || c2 == c3 || c2 == c4 {
-                            continue;
-                        }
-
-                        checking.retain(|x| {
-                            if has_solution(map1, map2, *x as DiceValue) {
-                                let _ = found.insert(*x);
-                                false
-                            } else {
-                                true
-                            }
-                        });
-                    }
-                }
-            }
+    pub fn print_solution_amount(&self) {
+        if self.solutions.len() == self.raw_solution_count {
+            println!("\n{} Lösungen gefunden\n", self.solutions.len());
+        } else {
+            println!(
+                "\n{} Lösungen gefunden, {} ohne Zusammenfassen gleichwertiger Lösungen\n",
+                self.solutions.len(),
+                self.raw_solution_count
+            );
         }
+    }
 
-        // three cubes
-        let mut maps = Vec::new();
-        for (cubes1, map1) in dp.iter() {
-            if let UsedCubes::OneCube(c1) = cubes1 {
-                for (cubes2, map2) in dp.iter() {
-                    if let UsedCubes::TwoCubes(c2, c3) = cubes2 {
-                        if c1 == c2 || c1 == c3 {
-                            continue;
-                        }
-
-                        let mut map: HashMap<DiceValue, Calculation> = self
-                            .result_map_pool
-                            .pop()
-                            .or_else(|| Some(HashMap::new()))
-                            .unwrap();
-                        calculate_result_map(map1, map2, &mut map);
-                        maps.push((UsedCubes::ThreeCubes(*c1, *c2, *c3), map));
-                    }
-                }
+    /// Prints the results nearest to the target. After a cancelled search
+    /// they are marked, as a closer result may not have been tried yet.
+    pub fn print_closest(&self) {
+        if self.incomplete {
+            if self.closest.is_empty() {
+                println!("\nBis zum Zeitlimit wurde kein Ergebnis berechnet\n");
+                return;
             }
+            println!("\nBis zum Zeitlimit gefunden, es gibt vielleicht nähere Ergebnisse:");
         }
+        self.closest.print();
+    }
 
-        while let Some((c, m)) = maps.pop() {
-            dp.insert(c, m);
-        }
+    pub fn print_solutions(&self) {
+        let mut s = self.solutions.iter().collect::<Vec<_>>();
+        s.sort_by_cached_key(|a| (self.difficulty.score(a), *a));
 
-        // result of calculation of three cubes and single cube
-        for (cubes1, map1) in dp.iter() {
-            if let UsedCubes::OneCube(c1) = cubes1 {
-                for (cubes2, map2) in dp.iter() {
-                    if let UsedCubes::ThreeCubes(c2, c3, c4) = cubes2 {
-                        if c1 == c2 || c1 == c3 || c1 == c4 {
-                            continue;
-                        }
-
-                        checking.retain(|x| {
-                            if has_solution(map1, map2, *x as DiceValue) {
-                                let _ = found.insert(*x);
-                                false
-                            } else {
-                                true
-                            }
-                        });
-                    }
-                }
-            }
+        if s.len() >= 2 {
+            println!("\nEinfachste Lösung: {}", s[0]);
+            println!("Schwierigste Lösung: {}\n", s[s.len() - 1]);
+        } else {
+            println!();
         }
 
-        for (_, mut map1) in self.dp.drain() {
-            map1.clear();
-            self.result_map_pool.push(map1);
+        println!("Alle {} Lösungen:", s.len());
+        for solution in &s {
+            println!("\t{} = {}", solution, self.num);
         }
-
-        let mut found = found.iter().collect::<Vec<_>>();
-        found.sort();
-        solutions.clear();
-        solutions.extend(found.iter().map(|&&x| x as u16));
+        println!("\n");
     }
+}
 
-    fn solve_three(&mut self) {
-        let n = self.num.clone();
-        let mut ds: [DiceValue; 3] = [0; 3];
-        ds[0] = self.dices[0] as DiceValue;
-        ds[1] = self.dices[1] as DiceValue;
-        ds[2] = self.dices[2] as DiceValue;
-
-        // single cubes
-        for (i, c) in ds.iter().enumerate() {
-            let mut map: HashMap<DiceValue, Calculation> = self
-                .result_map_pool
-                .pop()
-                .or_else(|| Some(HashMap::new()))
-                .unwrap();
-
-            map.insert(*c, Calculation::Cube(i, *c));
-            map.insert((*c) * 10, Calculation::Cube(i, (*c) * 10));
-            map.insert((*c) * 100, Calculation::Cube(i, (*c) * 100));
-            map.insert((*c) * 1000, Calculation::Cube(i, (*c) * 1000));
-
-            map.insert((*c) * 10000, Calculation::Cube(i, (*c) * 10000));
-            map.insert((*c) * 100000, Calculation::Cube(i, (*c) * 100000));
-            map.insert((*c) * 1000000, Calculation::Cube(i, (*c) * 1000000));
-            map.insert((*c) * 10000000, Calculation::Cube(i, (*c) * 10000000));
-
-            self.dp.insert(UsedCubes::OneCube(i as DiceValue), map);
-        }
-
-        // two cubes
-        let mut maps = Vec::new();
-
-        for (c1, map1) in self.dp.iter() {
-            for (c2, map2) in self.dp.iter() {
-                let UsedCubes::OneCube(c1) = c1 else { panic!() };
-                let UsedCubes::OneCube(c2) = c2 else { panic!() };
-
-                if c1 <= c2 {
-                    continue;
-                }
-
-                let mut map: HashMap<DiceValue, Calculation> = self
-                    .result_map_pool
-                    .pop()
-                    .or_else(|| Some(HashMap::new()))
-                    .unwrap();
+impl<V: Value> Dp<V> {
+    fn solve(
+        &mut self,
+        dices: &[u8],
+        rules: &Rules,
+        n: u64,
+        solutions: &mut HashSet<Calculation>,
+        closest: &mut Closest,
+        stop: &Stop,
+    ) {
+        let full = self.fill(dices, rules, stop);
 
-                calculate_result_map(map1, map2, &mut map);
-                maps.push((UsedCubes::TwoCubes(*c1, *c2), map));
+        // all cubes, only the combinations hitting the target are kept
+        if let Some(n) = V::from_int(n) {
+            for (part1, part2) in splits(full) {
+                let (map1, map2) = (&self.maps[part1], &self.maps[part2]);
+                check_for_solutions(map1, map2, rules, &self.arena, solutions, n, stop);
             }
-        }
 
-        self.dp.extend(maps);
-
-        // three cubes
-        for (cubes1, map1) in self.dp.iter() {
-            if let UsedCubes::OneCube(c1) = cubes1 {
-                for (cubes2, map2) in self.dp.iter() {
-                    if let UsedCubes::TwoCubes(c2, c3) = cubes2 {
-                        if c1 == c2 || c1 == c3 {
-                            continue;
-                        }
-
-                        check_for_solutions(map1, map2, &mut self.solutions, n.clone());
-                    }
+            // remember the misses in case the target is not reached
+            if solutions.is_empty() {
+                for (part1, part2) in splits(full) {
+                    let (map1, map2) = (&self.maps[part1], &self.maps[part2]);
+                    check_for_closest(map1, map2, rules, &self.arena, n, closest, stop);
                 }
             }
         }
 
-        for (_, mut map1) in self.dp.drain() {
-            map1.clear();
-            self.result_map_pool.push(map1);
-        }
+        self.release();
     }
 
-    fn solve_four(&mut self) {
-        let n = self.num.clone();
-        let mut ds: [DiceValue; 4] = [0; 4];
-        ds[0] = self.dices[0] as DiceValue;
-        ds[1] = self.dices[1] as DiceValue;
-        ds[2] = self.dices[2] as DiceValue;
-        ds[3] = self.dices[3] as DiceValue;
-
-        let mut dp: HashMap<UsedCubes, HashMap<DiceValue, Calculation>> = HashMap::new();
-
-        // single cubes
-        for (i, c) in ds.iter().enumerate() {
-            let mut map: HashMap<DiceValue, Calculation> = HashMap::new();
-            map.insert(*c, Calculation::Cube(i, *c));
-            map.insert((*c) * 10, Calculation::Cube(i, (*c) * 10));
-            map.insert((*c) * 100, Calculation::Cube(i, (*c) * 100));
-            map.insert((*c) * 1000, Calculation::Cube(i, (*c) * 1000));
-
-            map.insert((*c) * 10000, Calculation::Cube(i, (*c) * 10000));
-            map.insert((*c) * 100000, Calculation::Cube(i, (*c) * 100000));
-            map.insert((*c) * 1000000, Calculation::Cube(i, (*c) * 1000000));
-            map.insert((*c) * 10000000, Calculation::Cube(i, (*c) * 10000000));
-
-            dp.insert(UsedCubes::OneCube(i as DiceValue), map);
+    /// Fills the result maps of every proper subset of the dice and returns
+    /// the mask of all dice. The maps not reached before `stop` stay empty.
+    fn fill(&mut self, dices: &[u8], rules: &Rules, stop: &Stop) -> usize {
+        let full = (1usize << dices.len()) - 1;
+        while self.maps.len() <= full {
+            let map = self.pool.pop().unwrap_or_default();
+            self.maps.push(map);
         }
 
-        // two cubes
-        let mut maps = Vec::new();
-
-        for (c1, map1) in dp.iter() {
-            for (c2, map2) in dp.iter() {
-                let UsedCubes::OneCube(c1) = c1 else { panic!() };
-                let UsedCubes::OneCube(c2) = c2 else { panic!() };
-
-                if c1 <= c2 {
-                    continue;
+        // single cubes
+        for (i, c) in dices.iter().enumerate() {
+            let map = &mut self.maps[1 << i];
+            for value in rules.die_values((*c).into()) {
+                if let Some(v) = V::from_int(value) {
+                    map.insert(v, self.arena.push(Node::Cube(i, value)));
                 }
-
-                let mut map: HashMap<DiceValue, Calculation> = self
-                    .result_map_pool
-                    .pop()
-                    .or_else(|| Some(HashMap::new()))
-                    .unwrap();
-                calculate_result_map(map1, map2, &mut map);
-                maps.push((UsedCubes::TwoCubes(*c1, *c2), map));
             }
+            apply_unary(map, rules, false, &mut self.arena);
         }
 
-        while let Some((c, m)) = maps.pop() {
-            dp.insert(c, m);
-        }
-
-        // result of calculation of two cubes twice
-        for (cubes1, map1) in dp.iter() {
-            for (cubes2, map2) in dp.iter() {
-                if let UsedCubes::TwoCubes(c1, c2) = cubes1 {
-                    if let UsedCubes::TwoCubes(c3, c4) = cubes2 {
-                        if c1 == c3 || c1 == c4 || c2 == c3 || c2 == c4 {
-                            continue;
-                        }
-
-                        check_for_solutions(map1, map2, &mut self.solutions, n.clone());
-                    }
-                }
+        // every proper subset of at least two cubes, combined from all of its
+        // splits into two disjoint parts
+        for mask in 1..full {
+            if mask.count_ones() < 2 {
+                continue;
             }
-        }
-
-        // three cubes
-        let mut maps = Vec::new();
-        for (cubes1, map1) in dp.iter() {
-            if let UsedCubes::OneCube(c1) = cubes1 {
-                for (cubes2, map2) in dp.iter() {
-                    if let UsedCubes::TwoCubes(c2, c3) = cubes2 {
-                        if c1 == c2 || c1 == c3 {
-                            continue;
-                        }
-
-                        let mut map: HashMap<DiceValue, Calculation> = self
-                            .result_map_pool
-                            .pop()
-                            .or_else(|| Some(HashMap::new()))
-                            .unwrap();
-                        calculate_result_map(map1, map2, &mut map);
-                        maps.push((UsedCubes::ThreeCubes(*c1, *c2, *c3), map));
-                    }
-                }
+            if stop.now() {
+                break;
             }
-        }
 
-        while let Some((c, m)) = maps.pop() {
-            dp.insert(c, m);
-        }
-
-        // result of calculation of three cubes and single cube
-        for (cubes1, map1) in dp.iter() {
-            if let UsedCubes::OneCube(c1) = cubes1 {
-                for (cubes2, map2) in dp.iter() {
-                    if let UsedCubes::ThreeCubes(c2, c3, c4) = cubes2 {
-                        if c1 == c2 || c1 == c3 || c1 == c4 {
-                            continue;
-                        }
-
-                        check_for_solutions(map1, map2, &mut self.solutions, n.clone());
-                    }
-                }
+            let mut map = std::mem::take(&mut self.maps[mask]);
+            for (part1, part2) in splits(mask) {
+                let (map1, map2) = (&self.maps[part1], &self.maps[part2]);
+                calculate_result_map(map1, map2, &mut map, rules, &mut self.arena, stop);
             }
+            apply_unary(&mut map, rules, false, &mut self.arena);
+            self.maps[mask] = map;
         }
-    }
-
-    pub fn print_game(&self) {
-        println!("{}", self);
-    }
 
-    pub fn print_solution_amount(&self) {
-        println!("\n{} Lösungen gefunden\n", self.solutions.len());
+        full
     }
 
-    pub fn print_solutions(&self) {
-        let mut s = self.solutions.iter().collect::<Vec<_>>();
-        s.sort_by(|a, b| a.score().cmp(&b.score()));
-
-        if s.len() >= 2 {
-            println!("\nEinfachste Lösung: {}", s[0]);
-            println!("Schwierigste Lösung: {}\n", s[s.len() - 1]);
-        } else {
-            println!("");
-        }
-
-        println!("Alle {} Lösungen:", s.len());
-        for solution in &s {
-            println!("\t{} = {}", solution, self.num);
+    fn release(&mut self) {
+        for mut map in self.maps.drain(..) {
+            map.clear();
+            self.pool.push(map);
         }
-        println!("\n");
+        self.arena.clear();
     }
 }
 
-fn calculate_result_map(
-    map1: &HashMap<DiceValue, Calculation>,
-    map2: &HashMap<DiceValue, Calculation>,
-    result_map: &mut HashMap<DiceValue, Calculation>,
+fn calculate_result_map<V: Value>(
+    map1: &HashMap<V, NodeId>,
+    map2: &HashMap<V, NodeId>,
+    result_map: &mut HashMap<V, NodeId>,
+    rules: &Rules,
+    arena: &mut Arena,
+    stop: &Stop,
 ) {
-    // TODO:
-    assert!(result_map.is_empty());
-    result_map.clear();
-
-    for (res1, calc1) in map1.iter() {
-        for (res2, calc2) in map2.iter() {
-            if let Some(add) = res1.checked_add(*res2) {
-                if res1 >= res2 {
-                    result_map.insert(
-                        add,
-                        Calculation::Add(Box::new(calc1.clone()), Box::new(calc2.clone())),
-                    );
-                } else {
-                    result_map.insert(
-                        add,
-                        Calculation::Add(Box::new(calc2.clone()), Box::new(calc1.clone())),
-                    );
-                }
-            }
-
-            if res1 >= res2 {
-                if let Some(sub) = res1.checked_sub(*res2) {
-                    result_map.insert(
-                        sub,
-                        Calculation::Sub(Box::new(calc1.clone()), Box::new(calc2.clone())),
-                    );
-                }
-            }
-            if res2 >= res1 {
-                if let Some(sub) = res2.checked_sub(*res1) {
-                    result_map.insert(
-                        sub,
-                        Calculation::Sub(Box::new(calc2.clone()), Box::new(calc1.clone())),
-                    );
-                }
-            }
-
-            if let Some(mult) = res1.checked_mul(*res2) {
-                if res1 >= res2 {
-                    result_map.insert(
-                        mult,
-                        Calculation::Mul(Box::new(calc1.clone()), Box::new(calc2.clone())),
-                    );
+    for (res1, id1) in map1.iter() {
+        if stop.now() {
+            return;
+        }
+        for (res2, id2) in map2.iter() {
+            for (value, op, swapped) in combinations(rules, *res1, *res2) {
+                let node = if swapped {
+                    Node::Binary(op, *id2, *id1)
                 } else {
-                    result_map.insert(
-                        mult,
-                        Calculation::Mul(Box::new(calc2.clone()), Box::new(calc1.clone())),
-                    );
-                }
-            }
-
-            if *res2 > 0 {
-                if *res1 % 10 == 0 && *res2 % 10 == 0 && *res1 > 0 {
-                    continue;
-                }
-                if let Some(div) = res1.checked_div(*res2) {
-                    if div * *res2 == *res1 {
-                        result_map.insert(
-                            div,
-                            Calculation::Div(Box::new(calc1.clone()), Box::new(calc2.clone())),
-                        );
-                    }
-                }
-            }
-            if *res1 > 0 {
-                if *res1 % 10 == 0 && *res2 % 10 == 0 && *res2 > 0 {
-                    continue;
-                }
-                if let Some(div) = res2.checked_div(*res1) {
-                    if div * *res1 == *res2 {
-                        result_map.insert(
-                            div,
-                            Calculation::Div(Box::new(calc2.clone()), Box::new(calc1.clone())),
-                        );
+                    Node::Binary(op, *id1, *id2)
+                };
+                match result_map.get(&value) {
+                    // the map is still being filled, so nothing refers to
+                    // the node yet
+                    Some(&id) => arena.replace(id, node),
+                    None => {
+                        result_map.insert(value, arena.push(node));
                     }
                 }
             }
@@ -596,166 +313,81 @@ fn calculate_result_map(
     }
 }
 
-fn check_for_solutions(
-    map1: &HashMap<DiceValue, Calculation>,
-    map2: &HashMap<DiceValue, Calculation>,
+/// Adds every combination of a result of `map1` and one of `map2` that
+/// hits `n` to `solutions`, see [`final_pairs`].
+fn check_for_solutions<V: Value>(
+    map1: &HashMap<V, NodeId>,
+    map2: &HashMap<V, NodeId>,
+    rules: &Rules,
+    arena: &Arena,
     solutions: &mut HashSet<Calculation>,
-    n: DiceValue,
+    n: V,
+    stop: &Stop,
 ) {
-    for (r1, calc1) in map1 {
-        for (r2, calc2) in map2 {
-            if let Some(add) = r1.checked_add(*r2) {
-                if add == n {
-                    solutions.insert(Calculation::Add(
-                        Box::new(calc1.clone()),
-                        Box::new(calc2.clone()),
-                    ));
-                }
-            }
-
-            if r1 >= r2 {
-                if let Some(sub) = r1.checked_sub(*r2) {
-                    if sub == n {
-                        solutions.insert(Calculation::Sub(
-                            Box::new(calc1.clone()),
-                            Box::new(calc2.clone()),
-                        ));
-                    }
-                }
-            }
-
-            if r2 >= r1 {
-                if let Some(sub) = r2.checked_sub(*r1) {
-                    if sub == n {
-                        solutions.insert(Calculation::Sub(
-                            Box::new(calc2.clone()),
-                            Box::new(calc1.clone()),
-                        ));
-                    }
-                }
-            }
+    for ((r1, id1), (r2, id2)) in final_pairs(map1, map2, rules, n) {
+        if stop.now() {
+            return;
+        }
+        for (value, op, swapped) in combinations(rules, *r1, *r2) {
+            let calc = || binary(arena, op, swapped, *id1, *id2);
 
-            if let Some(mult) = r1.checked_mul(*r2) {
-                if mult == n {
-                    solutions.insert(Calculation::Mul(
-                        Box::new(calc1.clone()),
-                        Box::new(calc2.clone()),
-                    ));
-                }
+            if value == n {
+                solutions.insert(calc());
             }
 
-            if *r2 > 0 {
-                if *r1 % 10 == 0 && *r2 % 10 == 0 && *r1 > 0 {
-                    continue;
-                }
-                if let Some(div) = r1.checked_div(*r2) {
-                    if div * *r2 == *r1 {
-                        if div as DiceValue == n {
-                            solutions.insert(Calculation::Div(
-                                Box::new(calc1.clone()),
-                                Box::new(calc2.clone()),
-                            ));
-                        }
-                    }
-                }
-            }
-            if *r1 > 0 {
-                if *r1 % 10 == 0 && *r2 % 10 == 0 && *r2 > 0 {
-                    continue;
-                }
-                if let Some(div) = r2.checked_div(*r1) {
-                    if div * *r1 == *r2 {
-                        if div as DiceValue == n {
-                            solutions.insert(Calculation::Div(
-                                Box::new(calc2.clone()),
-                                Box::new(calc1.clone()),
-                            ));
-                        }
-                    }
+            // a unary operator may also be applied to the final result
+            for (result, unary_op) in unary_combinations(rules, value) {
+                if result == n {
+                    solutions.insert(Calculation::unary(unary_op, calc()));
                 }
             }
         }
     }
 }
 
-fn has_solution(
-    map1: &HashMap<DiceValue, Calculation>,
-    map2: &HashMap<DiceValue, Calculation>,
-    n: DiceValue,
-) -> bool {
-    for (r1, _) in map1 {
-        for (r2, _) in map2 {
-            if let Some(add) = r1.checked_add(*r2) {
-                if add == n {
-                    return true;
-                }
-            }
-
-            if r1 >= r2 {
-                if let Some(sub) = r1.checked_sub(*r2) {
-                    if sub == n {
-                        return true;
-                    }
-                }
-            }
+impl Solver for Game {
+    fn solve_for(&mut self, num: u64, dices: &[u64]) -> Vec<Calculation> {
+        let Some(dices) = to_dice_bytes(dices) else {
+            return Vec::new();
+        };
+        self.solve(num, &dices);
 
-            if r2 >= r1 {
-                if let Some(sub) = r2.checked_sub(*r1) {
-                    if sub == n {
-                        return true;
-                    }
-                }
-            }
+        self.solutions.iter().cloned().collect()
+    }
 
-            if let Some(mult) = r1.checked_mul(*r2) {
-                if mult == n {
-                    return true;
-                }
-            }
+    fn reachable(&mut self, range: Range<u64>, dices: &[u64]) -> Vec<u64> {
+        let Some(dices) = to_dice_bytes(dices) else {
+            return Vec::new();
+        };
 
-            if *r2 > 0 {
-                if *r1 % 10 == 0 && *r2 % 10 == 0 && *r1 > 0 {
-                    continue;
-                }
-                if let Some(div) = r1.checked_div(*r2) {
-                    if div * *r2 == *r1 {
-                        if div as DiceValue == n {
-                            return true;
-                        }
-                    }
-                }
-            }
-            if *r1 > 0 {
-                if *r1 % 10 == 0 && *r2 % 10 == 0 && *r2 > 0 {
-                    continue;
-                }
-                if let Some(div) = r2.checked_div(*r1) {
-                    if div * *r1 == *r2 {
-                        if div as DiceValue == n {
-                            return true;
-                        }
-                    }
-                }
-            }
-        }
+        self.reachable_in(range, &dices)
     }
+}
 
-    return false;
+/// The dice as the bytes the engine works with. Larger dice are reported,
+/// as the engine can not find anything with them.
+fn to_dice_bytes(dices: &[u64]) -> Option<Vec<u8>> {
+    let bytes = dices
+        .iter()
+        .map(|&d| u8::try_from(d).ok())
+        .collect::<Option<Vec<_>>>();
+    if bytes.is_none() {
+        eprintln!(
+            "Der schnelle Löser kann nur Würfel bis 255 benutzen: {:?}",
+            dices
+        );
+    }
+    bytes
 }
 
 impl std::fmt::Display for Game {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let dices = match self.num_dices {
-            DiceAmount::Three => &self.dices[0..3],
-            DiceAmount::Four => &self.dices,
-        };
-
-        let dices: Vec<_> = dices.iter().map(|&d| d.into()).collect();
+        let dices: Vec<_> = self.dices.iter().map(|&d| d.into()).collect();
 
         write!(
             f,
             "\n{}\n\n{}\n",
-            Output::number_string(self.num.into()),
+            Output::number_string(self.num),
             Output::dices_string(&dices)
         )
     }
@@ -1,275 +1,442 @@
 use anyhow::Result;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use std::{
     collections::HashMap,
-    sync::mpsc::{Receiver, Sender},
+    hash::{BuildHasherDefault, DefaultHasher},
+    ops::{Range, RangeInclusive},
+    sync::mpsc::Sender,
 };
 
-use crate::{game::calculation::Calculation, output::Output};
+use crate::{
+    arena::{Arena, Node, NodeId},
+    calculation::Calculation,
+    daily,
+    difficulty::{Difficulty, DifficultyModel, Level, Rating},
+    hint,
+    operator::{combinations, unary_combinations},
+    output::Output,
+    parser::parse,
+    results::{apply_unary, binary},
+    rules::Rules,
+    solver::{final_pairs, CancelToken, Closest, Solver, Stop},
+    subsets::splits,
+    table::Table,
+    value::{Rational, Value},
+};
+
+mod solutions;
 
-mod calculation;
+pub use solutions::{Finished, Solutions};
 
+#[derive(Default)]
 pub struct Game {
-    pub num_dices: DiceAmount,
     pub num: u64,
-    pub dices: [u64; 4],
+    pub dices: Vec<u64>,
     pub solutions: Vec<Calculation>,
+    pub rules: Rules,
+
+    /// Keep every derivation of each intermediate value instead of only one,
+    /// so that `solutions` contains every distinct solution. This grows
+    /// quickly with the amount of dice.
+    pub all_solutions: bool,
+
+    /// Keep solutions that only differ by reordering, like `((a + b) + c)`
+    /// and `(a + (b + c))`, instead of only the first of them.
+    pub keep_equivalent: bool,
+
+    /// Amount of solutions before equivalent ones were collapsed.
+    pub raw_solution_count: usize,
+
+    /// The results nearest to `num`, if it can not be reached exactly.
+    pub closest: Closest,
+
+    /// Rates the solutions for sorting them and choosing the easiest and
+    /// the hardest.
+    pub difficulty: Difficulty,
+
+    /// Seed the game was generated from, if any. The same seed, amount of
+    /// dice and rules generate the same game again, together with `level`
+    /// and the table if the game was generated with them.
+    pub seed: Option<u64>,
+
+    /// Level the game was generated for, see [`Game::generate`].
+    pub level: Option<Level>,
+
+    /// Whether rolls a table knows to be unsolvable were skipped, see
+    /// [`Game::random_solvable`].
+    pub checked_by_table: bool,
+
+    /// Stops the search early, see [`CancelToken`].
+    pub cancel: CancelToken,
+
+    /// Whether the last search was stopped by `cancel` before it was done,
+    /// so `solutions` or the reachable values may be missing some.
+    pub incomplete: bool,
 }
 
-pub enum DiceAmount {
-    Four,
-    Three,
+/// Outcome of checking the answer of a player.
+#[derive(Debug)]
+pub enum Answer {
+    Correct(Calculation),
+
+    /// A valid calculation that misses the target.
+    Wrong {
+        calc: Calculation,
+        value: u64,
+        distance: u64,
+    },
 }
 
-#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
-enum UsedCubes {
-    OneCube(u64),
-    TwoCubes(u64, u64),
-    ThreeCubes(u64, u64, u64),
+/// Games rolled at most when generating a game for a level or a solvable
+/// one, the puzzle of the day included.
+pub const MAX_ATTEMPTS: usize = 200;
+
+/// Range from which a random target is drawn for the given amount of dice.
+///
+/// Three dice aim for a 2-digit number, four dice for a 3-digit number and
+/// every additional die adds another digit.
+pub fn target_range(num_dices: usize) -> RangeInclusive<u64> {
+    match num_dices {
+        0..=3 => 1..=99,
+        n => 10u64.pow(n as u32 - 2)..=10u64.pow(n as u32 - 1) - 1,
+    }
 }
 
 impl Game {
-    pub fn new(num_dices: DiceAmount) -> Self {
-        let num: u64 = match num_dices {
-            DiceAmount::Three => rand::random_range(1..=99),
-            DiceAmount::Four => rand::random_range(100..=999),
-        };
-
-        let dices = [
-            rand::random_range(1..=6),
-            rand::random_range(1..=6),
-            rand::random_range(1..=6),
-            rand::random_range(1..=6),
-        ];
+    pub fn new(num_dices: usize) -> Self {
+        Self::random(num_dices, Rules::default(), &mut rand::rng())
+    }
 
-        Self {
-            num_dices,
-            num,
-            dices,
-            solutions: Vec::new(),
-        }
+    /// A random target and dice rolled with the die of `rules`.
+    pub fn random(num_dices: usize, rules: Rules, rng: &mut impl Rng) -> Self {
+        let num = rng.random_range(target_range(num_dices));
+
+        Self::roll(num_dices, num, rules, rng)
     }
 
-    pub fn of(num_dices: DiceAmount, num: u64, dices: [u64; 4]) -> Self {
+    /// [`Game::random`] drawn from `seed`.
+    pub fn seeded(num_dices: usize, rules: Rules, seed: u64) -> Self {
+        let game = Self::random(num_dices, rules, &mut StdRng::seed_from_u64(seed));
+
         Self {
-            num_dices,
-            num,
-            dices,
-            solutions: Vec::new(),
+            seed: Some(seed),
+            ..game
         }
     }
 
-    pub fn of_number(num_dices: DiceAmount, num: u64) -> Self {
-        let dices = [
-            rand::random_range(1..=6),
-            rand::random_range(1..=6),
-            rand::random_range(1..=6),
-            rand::random_range(1..=6),
-        ];
+    /// The puzzle of `day`, see [`daily::today`]. Everyone playing with the
+    /// same amount of dice and rules gets the same solvable game of medium
+    /// difficulty, the game of medium difficulty generated from the seed of
+    /// the day. Generating it stops with `cancel`, see [`Game::generate`].
+    pub fn daily(num_dices: usize, rules: Rules, day: u64, cancel: &CancelToken) -> Self {
+        let seed = daily::seed(day);
+        let mut rng = StdRng::seed_from_u64(seed);
+        let game = Self::generate(
+            num_dices,
+            Level::Medium,
+            rules,
+            MAX_ATTEMPTS,
+            None,
+            cancel,
+            &mut rng,
+        );
 
         Self {
-            num_dices,
-            num,
-            dices,
-            solutions: Vec::new(),
+            seed: Some(seed),
+            ..game
         }
     }
 
-    pub fn solve(&mut self) {
-        match self.num_dices {
-            DiceAmount::Three => self.solve_three(),
-            DiceAmount::Four => self.solve_four(),
-        }
-    }
+    /// Rolls games until one is solvable and its rating falls into `level`,
+    /// see [`Rating`]. The returned game is already solved.
+    ///
+    /// After `max_attempts` the solvable game rated closest to the band is
+    /// returned, or the last one if none of them was solvable. Its `level`
+    /// is still the requested one, so its seed repeats it, but its
+    /// [`Game::rating`] tells the band it really falls into, see
+    /// [`Game::missed_level`]. Games that `table` knows to be unsolvable are
+    /// skipped without solving them.
+    ///
+    /// The games are solved with `cancel`, which also stops the generation.
+    /// A game whose search was stopped is returned if there is no better
+    /// one, marked as incomplete.
+    pub fn generate(
+        num_dices: usize,
+        level: Level,
+        rules: Rules,
+        max_attempts: usize,
+        table: Option<&Table>,
+        cancel: &CancelToken,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let table = table.filter(|table| table.fits(num_dices, &rules));
+        let mut best: Option<(u32, Self)> = None;
+        let mut unrated = None;
+
+        for _ in 0..max_attempts.max(1) {
+            let mut game = Self::random(num_dices, rules.clone(), rng);
+            game.cancel = cancel.clone();
+            if table.and_then(|table| table.is_solvable(game.num, &game.dices)) == Some(false) {
+                unrated = Some(game);
+                continue;
+            }
+            game.solve();
 
-    fn solve_three(&mut self) {
-        let (tx, rx): (Sender<Calculation>, Receiver<Calculation>) = std::sync::mpsc::channel();
-        let n = self.num.clone();
-        let mut ds: [u64; 3] = [0; 3];
-        ds.copy_from_slice(&self.dices[0..3]);
+            if game.incomplete {
+                // out of time, its rating is not known
+                unrated = Some(game);
+                break;
+            }
+            let Some(rating) = game.rating() else {
+                unrated = Some(game);
+                continue;
+            };
+            let distance = level.distance(rating.score);
+            if distance == 0 {
+                return Self {
+                    level: Some(level),
+                    ..game
+                };
+            }
+            if best.as_ref().is_none_or(|(best, _)| distance < *best) {
+                best = Some((distance, game));
+            }
+        }
 
-        std::thread::spawn(move || {
-            let mut dp: HashMap<UsedCubes, HashMap<u64, Calculation>> = HashMap::new();
+        let game = best
+            .map(|(_, game)| game)
+            .or(unrated)
+            .unwrap_or_else(|| Self::random(num_dices, rules, rng));
 
-            // single cubes
-            for (i, c) in ds.iter().enumerate() {
-                let mut map: HashMap<u64, Calculation> = HashMap::new();
-                map.insert(*c, Calculation::Cube(i, *c));
-                map.insert((*c) * 10, Calculation::Cube(i, (*c) * 10));
-                map.insert((*c) * 100, Calculation::Cube(i, (*c) * 100));
-                map.insert((*c) * 1000, Calculation::Cube(i, (*c) * 1000));
+        Self {
+            level: Some(level),
+            ..game
+        }
+    }
 
-                map.insert((*c) * 10000, Calculation::Cube(i, (*c) * 10000));
-                map.insert((*c) * 100000, Calculation::Cube(i, (*c) * 100000));
-                map.insert((*c) * 1000000, Calculation::Cube(i, (*c) * 1000000));
-                map.insert((*c) * 10000000, Calculation::Cube(i, (*c) * 10000000));
+    /// Rolls games until `table` knows one to be solvable, at most
+    /// `max_attempts` times. Without a fitting table the first game is
+    /// returned.
+    pub fn random_solvable(
+        num_dices: usize,
+        rules: Rules,
+        table: &Table,
+        max_attempts: usize,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let mut game = Self::random(num_dices, rules.clone(), rng);
+        if !table.fits(num_dices, &rules) {
+            return game;
+        }
 
-                dp.insert(UsedCubes::OneCube(i as u64), map);
+        for _ in 1..max_attempts {
+            if table.is_solvable(game.num, &game.dices) != Some(false) {
+                break;
             }
+            game = Self::random(num_dices, rules.clone(), rng);
+        }
 
-            // two cubes
-            let mut maps = Vec::new();
+        Self {
+            checked_by_table: true,
+            ..game
+        }
+    }
 
-            for (c1, map1) in dp.iter() {
-                for (c2, map2) in dp.iter() {
-                    let UsedCubes::OneCube(c1) = c1 else { panic!() };
-                    let UsedCubes::OneCube(c2) = c2 else { panic!() };
+    pub fn of(num: u64, dices: Vec<u64>) -> Self {
+        Self {
+            num,
+            dices,
+            ..Self::default()
+        }
+    }
 
-                    if c1 <= c2 {
-                        continue;
-                    }
+    pub fn of_number(num_dices: usize, num: u64) -> Self {
+        Self::roll(num_dices, num, Rules::default(), &mut rand::rng())
+    }
 
-                    let map: HashMap<u64, Calculation> = calculate_result_map(map1, map2);
-                    maps.push((UsedCubes::TwoCubes(*c1, *c2), map));
-                }
-            }
+    /// The target `num` and dice rolled with the die of `rules`.
+    pub fn roll(num_dices: usize, num: u64, rules: Rules, rng: &mut impl Rng) -> Self {
+        let dices = (0..num_dices).map(|_| rules.die.roll(rng)).collect();
 
-            while let Some((c, m)) = maps.pop() {
-                dp.insert(c, m);
-            }
+        Self {
+            rules,
+            ..Self::of(num, dices)
+        }
+    }
 
-            // three cubes
-            for (cubes1, map1) in dp.iter() {
-                if let UsedCubes::OneCube(c1) = cubes1 {
-                    for (cubes2, map2) in dp.iter() {
-                        if let UsedCubes::TwoCubes(c2, c3) = cubes2 {
-                            if c1 == c2 || c1 == c3 {
-                                continue;
-                            }
-
-                            if let Err(e) = check_for_solutions(map1, map2, tx.clone(), n.clone()) {
-                                eprintln!("Kanalfehler {}", e);
-                            }
-                        }
-                    }
-                }
-            }
-        });
+    pub fn solve(&mut self) {
+        let mut solutions = self.solutions_iter();
+        self.solutions = solutions.by_ref().collect();
+        self.raw_solution_count = solutions.raw_count();
+        let finished = solutions.finish();
+        self.closest = finished.closest;
+        self.incomplete = finished.incomplete;
+        if !self.keep_equivalent {
+            self.closest.keep_canonical();
+        }
 
-        for received in rx {
-            if self.solutions.contains(&received) {
-                continue;
+        #[cfg(debug_assertions)]
+        for solution in &self.solutions {
+            if let Err(e) = solution.verify(self.num, &self.dices, &self.rules) {
+                panic!("Ungültige Lösung {}: {}", solution, e);
             }
-            self.solutions.push(received);
         }
     }
 
-    fn solve_four(&mut self) {
-        let (tx, rx): (Sender<Calculation>, Receiver<Calculation>) = std::sync::mpsc::channel();
-        let n = self.num.clone();
-        let ds = self.dices.clone();
-        std::thread::spawn(move || {
-            let mut dp: HashMap<UsedCubes, HashMap<u64, Calculation>> = HashMap::new();
+    /// The solutions, yielded as soon as they are found, see [`Solutions`].
+    /// Unlike [`Game::solve`] this does not change the game.
+    pub fn solutions_iter(&self) -> Solutions {
+        match (self.rules.rational, self.rules.negative) {
+            (true, _) => Solutions::spawn::<Rational>(self),
+            (false, true) => Solutions::spawn::<i64>(self),
+            (false, false) => Solutions::spawn::<u64>(self),
+        }
+    }
 
-            // single cubes
-            for (i, c) in ds.iter().enumerate() {
-                let mut map: HashMap<u64, Calculation> = HashMap::new();
-                map.insert(*c, Calculation::Cube(i, *c));
-                map.insert((*c) * 10, Calculation::Cube(i, (*c) * 10));
-                map.insert((*c) * 100, Calculation::Cube(i, (*c) * 100));
-                map.insert((*c) * 1000, Calculation::Cube(i, (*c) * 1000));
+    fn reachable_with<V: Value>(&mut self, range: Range<u64>, dices: &[u64]) -> Vec<u64> {
+        let full = (1usize << dices.len()) - 1;
+        let stop = Stop::new(&self.cancel);
+        let mut arena = Arena::new();
+        let dp = fill_dp::<V>(dices, &self.rules, false, &mut arena, &stop);
 
-                map.insert((*c) * 10000, Calculation::Cube(i, (*c) * 10000));
-                map.insert((*c) * 100000, Calculation::Cube(i, (*c) * 100000));
-                map.insert((*c) * 1000000, Calculation::Cube(i, (*c) * 1000000));
-                map.insert((*c) * 10000000, Calculation::Cube(i, (*c) * 10000000));
+        let mut map = ResultMap::default();
+        for (part1, part2) in splits(full) {
+            let (map1, map2) = (&dp[part1], &dp[part2]);
+            calculate_result_map(map1, map2, &mut map, &self.rules, false, &mut arena, &stop);
+        }
+        apply_unary(&mut map, &self.rules, false, &mut arena);
+        self.incomplete = stop.stopped();
+
+        let mut reachable = map
+            .into_keys()
+            .filter_map(V::to_int)
+            .filter(|v| range.contains(v))
+            .collect::<Vec<_>>();
+        reachable.sort();
+        reachable
+    }
 
-                dp.insert(UsedCubes::OneCube(i as u64), map);
-            }
+    /// Parses the answer of a player and checks it against the dice and the
+    /// target. Invalid input, like a die used twice, is an error.
+    pub fn check_answer(&self, input: &str) -> Result<Answer> {
+        let calc = parse(input, &self.dices, &self.rules)?;
+        let value = calc.value(&self.rules)?;
+        calc.verify(value, &self.dices, &self.rules)?;
 
-            // two cubes
-            let mut maps = Vec::new();
+        if value == self.num {
+            Ok(Answer::Correct(calc))
+        } else {
+            Ok(Answer::Wrong {
+                calc,
+                value,
+                distance: value.abs_diff(self.num),
+            })
+        }
+    }
 
-            for (c1, map1) in dp.iter() {
-                for (c2, map2) in dp.iter() {
-                    let UsedCubes::OneCube(c1) = c1 else { panic!() };
-                    let UsedCubes::OneCube(c2) = c2 else { panic!() };
+    /// Sorts the solutions from the easiest to the hardest.
+    pub fn sort_solutions(&mut self) {
+        let difficulty = self.difficulty;
+        self.solutions
+            .sort_by_cached_key(|solution| (difficulty.score(solution), solution.clone()));
+    }
 
-                    if c1 <= c2 {
-                        continue;
-                    }
+    /// Hints towards the easiest solution, see [`hint::hints`]. Empty if
+    /// there is no solution.
+    pub fn hints(&self) -> Vec<String> {
+        let easiest = self
+            .solutions
+            .iter()
+            .min_by_key(|solution| (self.difficulty.score(solution), *solution));
+        match easiest {
+            Some(easiest) => hint::hints(easiest, &self.difficulty),
+            None => Vec::new(),
+        }
+    }
 
-                    let map: HashMap<u64, Calculation> = calculate_result_map(map1, map2);
-                    maps.push((UsedCubes::TwoCubes(*c1, *c2), map));
-                }
-            }
+    /// Difficulty of the game, `None` if it has no solution.
+    pub fn rating(&self) -> Option<Rating> {
+        Rating::of(&self.solutions, &self.difficulty)
+    }
 
-            while let Some((c, m)) = maps.pop() {
-                dp.insert(c, m);
-            }
+    pub fn print_rating(&self) {
+        if let Some(rating) = self.rating() {
+            println!("Schwierigkeit des Spiels: {}\n", rating);
+        }
+    }
 
-            // result of calculation of two cubes twice
-            for (cubes1, map1) in dp.iter() {
-                for (cubes2, map2) in dp.iter() {
-                    if let UsedCubes::TwoCubes(c1, c2) = cubes1 {
-                        if let UsedCubes::TwoCubes(c3, c4) = cubes2 {
-                            if c1 == c3 || c1 == c4 || c2 == c3 || c2 == c4 {
-                                continue;
-                            }
-
-                            if let Err(e) = check_for_solutions(map1, map2, tx.clone(), n.clone()) {
-                                eprintln!("Kanalfehler {}", e);
-                            }
-                        }
-                    }
-                }
-            }
+    /// Whether the game was generated for a level it does not fall into,
+    /// because no attempt of [`Game::generate`] did. Only meaningful once
+    /// the game is solved.
+    pub fn missed_level(&self) -> bool {
+        self.level
+            .is_some_and(|level| self.rating().is_none_or(|rating| rating.level != level))
+    }
 
-            // three cubes
-            let mut maps = Vec::new();
-            for (cubes1, map1) in dp.iter() {
-                if let UsedCubes::OneCube(c1) = cubes1 {
-                    for (cubes2, map2) in dp.iter() {
-                        if let UsedCubes::TwoCubes(c2, c3) = cubes2 {
-                            if c1 == c2 || c1 == c3 {
-                                continue;
-                            }
-
-                            let map = calculate_result_map(map1, map2);
-                            maps.push((UsedCubes::ThreeCubes(*c1, *c2, *c3), map));
-                        }
-                    }
+    pub fn print_game(&self) {
+        println!("{}", self);
+        if let Some(level) = self.level.filter(|_| self.missed_level()) {
+            match self.rating() {
+                _ if self.incomplete => {
+                    println!(
+                        "Bis zum Zeitlimit wurde kein Spiel der Stufe {} gefunden",
+                        level
+                    )
                 }
+                Some(rating) => println!(
+                    "Kein Spiel der Stufe {} gefunden, dieses ist {}",
+                    level, rating.level
+                ),
+                None => println!("Kein lösbares Spiel der Stufe {} gefunden", level),
             }
-
-            while let Some((c, m)) = maps.pop() {
-                dp.insert(c, m);
-            }
-
-            // result of calculation of three cubes and single cube
-            for (cubes1, map1) in dp.iter() {
-                if let UsedCubes::OneCube(c1) = cubes1 {
-                    for (cubes2, map2) in dp.iter() {
-                        if let UsedCubes::ThreeCubes(c2, c3, c4) = cubes2 {
-                            if c1 == c2 || c1 == c3 || c1 == c4 {
-                                continue;
-                            }
-
-                            if let Err(e) = check_for_solutions(map1, map2, tx.clone(), n.clone()) {
-                                eprintln!("Kanalfehler {}", e);
-                            }
-                        }
-                    }
-                }
+        }
+        if let Some(seed) = self.seed {
+            let mut options = Vec::new();
+            if let Some(level) = self.level {
+                options.extend(["--level".to_string(), level.name().to_string()]);
             }
-        });
-
-        for received in rx {
-            if self.solutions.contains(&received) {
-                continue;
+            options.extend(["--seed".to_string(), seed.to_string()]);
+            options.extend(self.rules.options());
+            if self.checked_by_table {
+                options.extend(["--table".to_string(), "<Datei>".to_string()]);
             }
-            self.solutions.push(received);
+            println!(
+                "Startwert {} (mit {} wiederholbar)\n",
+                seed,
+                options.join(" ")
+            );
         }
     }
 
-    pub fn print_game(&self) {
-        println!("{}", self);
+    pub fn print_solution_amount(&self) {
+        if self.solutions.len() == self.raw_solution_count {
+            println!("\n{} Lösungen gefunden\n", self.solutions.len());
+        } else {
+            println!(
+                "\n{} Lösungen gefunden, {} ohne Zusammenfassen gleichwertiger Lösungen\n",
+                self.solutions.len(),
+                self.raw_solution_count
+            );
+        }
     }
 
-    pub fn print_solution_amount(&self) {
-        println!("\n{} Lösungen gefunden\n", self.solutions.len());
+    /// Prints the results nearest to the target. After a cancelled search
+    /// they are marked, as a closer result may not have been tried yet.
+    pub fn print_closest(&self) {
+        if self.incomplete {
+            if self.closest.is_empty() {
+                println!("\nBis zum Zeitlimit wurde kein Ergebnis berechnet\n");
+                return;
+            }
+            println!("\nBis zum Zeitlimit gefunden, es gibt vielleicht nähere Ergebnisse:");
+        }
+        self.closest.print();
     }
 
-    pub fn print_solutions(&self) {
+    /// Prints the first and the last solution, which are the easiest and
+    /// the hardest once the solutions are sorted, see
+    /// [`Game::sort_solutions`].
+    pub fn print_easiest_and_hardest(&self) {
         if self.solutions.len() >= 2 {
             println!("\nEinfachste Lösung: {}", self.solutions[0]);
             println!(
@@ -277,8 +444,12 @@ impl Game {
                 self.solutions[self.solutions.len() - 1]
             );
         } else {
-            println!("");
+            println!();
         }
+    }
+
+    pub fn print_solutions(&self) {
+        self.print_easiest_and_hardest();
 
         println!("Alle {} Lösungen:", self.solutions.len());
         for solution in &self.solutions {
@@ -288,186 +459,170 @@ impl Game {
     }
 }
 
-fn calculate_result_map(
-    map1: &HashMap<u64, Calculation>,
-    map2: &HashMap<u64, Calculation>,
-) -> HashMap<u64, Calculation> {
-    let mut result_map = HashMap::new();
-
-    for (res1, calc1) in map1.iter() {
-        for (res2, calc2) in map2.iter() {
-            if let Some(add) = res1.checked_add(*res2) {
-                if res1 >= res2 {
-                    result_map.insert(
-                        add,
-                        Calculation::Add(Box::new(calc1.clone()), Box::new(calc2.clone())),
-                    );
-                } else {
-                    result_map.insert(
-                        add,
-                        Calculation::Add(Box::new(calc2.clone()), Box::new(calc1.clone())),
-                    );
-                }
-            }
+/// Every calculation found for a value, as nodes of the arena of the solve.
+/// Unless all solutions are requested, only the last one found is kept.
+///
+/// The hasher is fixed, so that the maps are iterated in the same order on
+/// every run and a seeded game always gets the same solutions.
+type ResultMap<V> = HashMap<V, Vec<NodeId>, BuildHasherDefault<DefaultHasher>>;
+
+fn insert<V: Value>(map: &mut ResultMap<V>, arena: &mut Arena, all: bool, value: V, node: Node) {
+    let ids = map.entry(value).or_default();
+    match ids.last() {
+        // the map is still being filled, so nothing refers to the node yet
+        Some(&id) if !all => arena.replace(id, node),
+        _ => ids.push(arena.push(node)),
+    }
+}
 
-            if res1 >= res2 {
-                if let Some(sub) = res1.checked_sub(*res2) {
-                    result_map.insert(
-                        sub,
-                        Calculation::Sub(Box::new(calc1.clone()), Box::new(calc2.clone())),
-                    );
-                }
-            }
-            if res2 >= res1 {
-                if let Some(sub) = res2.checked_sub(*res1) {
-                    result_map.insert(
-                        sub,
-                        Calculation::Sub(Box::new(calc2.clone()), Box::new(calc1.clone())),
-                    );
-                }
+/// Result maps of every proper subset of `ds`, indexed by the bitmask of the
+/// used dice. The entry of the full set is left empty, as are the entries
+/// not reached before `stop`.
+fn fill_dp<V: Value>(
+    ds: &[u64],
+    rules: &Rules,
+    all: bool,
+    arena: &mut Arena,
+    stop: &Stop,
+) -> Vec<ResultMap<V>> {
+    let full = (1usize << ds.len()) - 1;
+    let mut dp: Vec<ResultMap<V>> = vec![ResultMap::default(); full + 1];
+
+    // single cubes
+    for (i, c) in ds.iter().enumerate() {
+        let map = &mut dp[1 << i];
+        for value in rules.die_values(*c) {
+            if let Some(v) = V::from_int(value) {
+                map.insert(v, vec![arena.push(Node::Cube(i, value))]);
             }
+        }
+        apply_unary(map, rules, all, arena);
+    }
 
-            if let Some(mult) = res1.checked_mul(*res2) {
-                if res1 >= res2 {
-                    result_map.insert(
-                        mult,
-                        Calculation::Mul(Box::new(calc1.clone()), Box::new(calc2.clone())),
-                    );
-                } else {
-                    result_map.insert(
-                        mult,
-                        Calculation::Mul(Box::new(calc2.clone()), Box::new(calc1.clone())),
-                    );
-                }
-            }
+    // every proper subset of at least two cubes, combined from all of its
+    // splits into two disjoint parts. Submasks are numerically smaller than
+    // their mask, so they are always filled in before.
+    for mask in 1..full {
+        if mask.count_ones() < 2 {
+            continue;
+        }
+        if stop.now() {
+            break;
+        }
 
-            if *res2 > 0 {
-                if *res1 % 10 == 0 && *res2 % 10 == 0 && *res1 > 0 {
-                    continue;
-                }
-                if let Some(div) = res1.checked_div(*res2) {
-                    if div * *res2 == *res1 {
-                        result_map.insert(
-                            div,
-                            Calculation::Div(Box::new(calc1.clone()), Box::new(calc2.clone())),
-                        );
-                    }
-                }
-            }
-            if *res1 > 0 {
-                if *res1 % 10 == 0 && *res2 % 10 == 0 && *res2 > 0 {
-                    continue;
-                }
-                if let Some(div) = res2.checked_div(*res1) {
-                    if div * *res1 == *res2 {
-                        result_map.insert(
-                            div,
-                            Calculation::Div(Box::new(calc2.clone()), Box::new(calc1.clone())),
-                        );
-                    }
-                }
-            }
+        let mut map = ResultMap::default();
+        for (part1, part2) in splits(mask) {
+            calculate_result_map(&dp[part1], &dp[part2], &mut map, rules, all, arena, stop);
         }
+        apply_unary(&mut map, rules, all, arena);
+        dp[mask] = map;
     }
 
-    return result_map;
+    dp
 }
 
-fn check_for_solutions(
-    map1: &HashMap<u64, Calculation>,
-    map2: &HashMap<u64, Calculation>,
-    tx: Sender<Calculation>,
-    n: u64,
-) -> Result<()> {
-    for (r1, calc1) in map1 {
-        for (r2, calc2) in map2 {
-            if let Some(add) = r1.checked_add(*r2) {
-                if add == n {
-                    tx.send(Calculation::Add(
-                        Box::new(calc1.clone()),
-                        Box::new(calc2.clone()),
-                    ))?;
-                }
-            }
-
-            if r1 >= r2 {
-                if let Some(sub) = r1.checked_sub(*r2) {
-                    if sub == n {
-                        tx.send(Calculation::Sub(
-                            Box::new(calc1.clone()),
-                            Box::new(calc2.clone()),
-                        ))?;
+fn calculate_result_map<V: Value>(
+    map1: &ResultMap<V>,
+    map2: &ResultMap<V>,
+    result_map: &mut ResultMap<V>,
+    rules: &Rules,
+    all: bool,
+    arena: &mut Arena,
+    stop: &Stop,
+) {
+    for (res1, ids1) in map1.iter() {
+        if stop.now() {
+            return;
+        }
+        for (res2, ids2) in map2.iter() {
+            for (value, op, swapped) in combinations(rules, *res1, *res2) {
+                for &id1 in ids1 {
+                    for &id2 in ids2 {
+                        let node = if swapped {
+                            Node::Binary(op, id2, id1)
+                        } else {
+                            Node::Binary(op, id1, id2)
+                        };
+                        insert(result_map, arena, all, value, node);
                     }
                 }
             }
+        }
+    }
+}
 
-            if r2 >= r1 {
-                if let Some(sub) = r2.checked_sub(*r1) {
-                    if sub == n {
-                        tx.send(Calculation::Sub(
-                            Box::new(calc2.clone()),
-                            Box::new(calc1.clone()),
-                        ))?;
-                    }
-                }
+/// Sends every combination of a result of `map1` and one of `map2` that
+/// hits `n` and returns how many were sent, see [`final_pairs`].
+fn check_for_solutions<V: Value>(
+    map1: &ResultMap<V>,
+    map2: &ResultMap<V>,
+    rules: &Rules,
+    arena: &Arena,
+    tx: &Sender<Calculation>,
+    n: V,
+    stop: &Stop,
+) -> Result<usize> {
+    let mut sent = 0;
+
+    for ((r1, ids1), (r2, ids2)) in final_pairs(map1, map2, rules, n) {
+        if stop.now() {
+            break;
+        }
+        for (value, op, swapped) in combinations(rules, *r1, *r2) {
+            // a unary operator may also be applied to the final result
+            let unary = unary_combinations(rules, value)
+                .filter(|(result, _)| *result == n)
+                .map(|(_, op)| op)
+                .collect::<Vec<_>>();
+            if value != n && unary.is_empty() {
+                continue;
             }
 
-            if let Some(mult) = r1.checked_mul(*r2) {
-                if mult == n {
-                    tx.send(Calculation::Mul(
-                        Box::new(calc1.clone()),
-                        Box::new(calc2.clone()),
-                    ))?;
-                }
-            }
+            for &id1 in ids1 {
+                for &id2 in ids2 {
+                    let calc = binary(arena, op, swapped, id1, id2);
 
-            if *r2 > 0 {
-                if *r1 % 10 == 0 && *r2 % 10 == 0 && *r1 > 0 {
-                    continue;
-                }
-                if let Some(div) = r1.checked_div(*r2) {
-                    if div * *r2 == *r1 {
-                        if div as u64 == n {
-                            tx.send(Calculation::Div(
-                                Box::new(calc1.clone()),
-                                Box::new(calc2.clone()),
-                            ))?;
-                        }
+                    for unary_op in &unary {
+                        tx.send(Calculation::unary(*unary_op, calc.clone()))?;
+                        sent += 1;
                     }
-                }
-            }
-            if *r1 > 0 {
-                if *r1 % 10 == 0 && *r2 % 10 == 0 && *r2 > 0 {
-                    continue;
-                }
-                if let Some(div) = r2.checked_div(*r1) {
-                    if div * *r1 == *r2 {
-                        if div as u64 == n {
-                            tx.send(Calculation::Div(
-                                Box::new(calc2.clone()),
-                                Box::new(calc1.clone()),
-                            ))?;
-                        }
+                    if value == n {
+                        tx.send(calc)?;
+                        sent += 1;
                     }
                 }
             }
         }
     }
-    Ok(())
+    Ok(sent)
+}
+
+impl Solver for Game {
+    fn solve_for(&mut self, num: u64, dices: &[u64]) -> Vec<Calculation> {
+        self.num = num;
+        self.dices = dices.to_vec();
+        self.solutions.clear();
+        self.solve();
+
+        self.solutions.clone()
+    }
+
+    fn reachable(&mut self, range: Range<u64>, dices: &[u64]) -> Vec<u64> {
+        match (self.rules.rational, self.rules.negative) {
+            (true, _) => self.reachable_with::<Rational>(range, dices),
+            (false, true) => self.reachable_with::<i64>(range, dices),
+            (false, false) => self.reachable_with::<u64>(range, dices),
+        }
+    }
 }
 
 impl std::fmt::Display for Game {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let dices = match self.num_dices {
-            DiceAmount::Three => &self.dices[0..3],
-            DiceAmount::Four => &self.dices,
-        };
-
         write!(
             f,
             "\n{}\n\n{}\n",
             Output::number_string(self.num),
-            Output::dices_string(dices)
+            Output::dices_string(&self.dices)
         )
     }
 }
@@ -0,0 +1,150 @@
+use std::{
+    collections::HashSet,
+    sync::mpsc::{Receiver, Sender},
+    thread::JoinHandle,
+};
+
+use crate::{
+    arena::Arena,
+    calculation::Calculation,
+    results::check_for_closest,
+    solver::{CancelToken, Closest, Stop},
+    subsets::splits,
+    value::Value,
+};
+
+use super::{check_for_solutions, fill_dp, Game};
+
+/// Solutions of a game, yielded while the search is still running, see
+/// [`Game::solutions_iter`].
+///
+/// Solutions that only differ by reordering are yielded once, unless the
+/// game keeps equivalent solutions. Dropping the iterator or cancelling the
+/// game stops the search.
+pub struct Solutions {
+    rx: Receiver<Calculation>,
+    handle: Option<JoinHandle<Finished>>,
+
+    /// Cancelled with the game, and when the iterator is dropped.
+    cancel: CancelToken,
+    keep_equivalent: bool,
+
+    /// Every solution received, equivalent ones included.
+    raw: HashSet<Calculation>,
+
+    /// Normal forms of the solutions yielded so far.
+    seen: HashSet<Calculation>,
+}
+
+/// What a search leaves besides its solutions, see [`Solutions::finish`].
+#[derive(Debug, Clone, Default)]
+pub struct Finished {
+    /// The results nearest to the target, if it was not reached.
+    pub closest: Closest,
+
+    /// Whether the search was cancelled before it was done.
+    pub incomplete: bool,
+}
+
+impl Solutions {
+    /// Starts searching the solutions of `game` on another thread.
+    pub(super) fn spawn<V: Value>(game: &Game) -> Self {
+        let (tx, rx): (Sender<Calculation>, Receiver<Calculation>) = std::sync::mpsc::channel();
+        let ds = game.dices.clone();
+        let rules = game.rules.clone();
+        let all = game.all_solutions;
+        let cancel = game.cancel.child();
+        let search = cancel.clone();
+
+        let handle = V::from_int(game.num).map(|n| {
+            std::thread::spawn(move || {
+                let full = (1usize << ds.len()) - 1;
+                let stop = Stop::new(&search);
+                let mut arena = Arena::new();
+                let dp = fill_dp::<V>(&ds, &rules, all, &mut arena, &stop);
+                let mut found = 0;
+
+                // all cubes, only the combinations hitting the target are kept
+                for (part1, part2) in splits(full) {
+                    let (map1, map2) = (&dp[part1], &dp[part2]);
+                    match check_for_solutions(map1, map2, &rules, &arena, &tx, n, &stop) {
+                        Ok(sent) => found += sent,
+                        // nobody is listening anymore
+                        Err(_) => return Finished::default(),
+                    }
+                }
+                drop(tx);
+
+                // remember the misses in case the target is not reached
+                let mut closest = Closest::new();
+                if found == 0 {
+                    for (part1, part2) in splits(full) {
+                        let (map1, map2) = (&dp[part1], &dp[part2]);
+                        check_for_closest(map1, map2, &rules, &arena, n, &mut closest, &stop);
+                    }
+                }
+
+                Finished {
+                    closest,
+                    incomplete: stop.stopped(),
+                }
+            })
+        });
+
+        Self {
+            rx,
+            handle,
+            cancel,
+            keep_equivalent: game.keep_equivalent,
+            raw: HashSet::new(),
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Amount of solutions received so far, before equivalent ones were
+    /// collapsed.
+    pub fn raw_count(&self) -> usize {
+        self.raw.len()
+    }
+
+    /// Waits for the search to finish, the remaining solutions are
+    /// dropped.
+    pub fn finish(mut self) -> Finished {
+        while self.rx.recv().is_ok() {}
+
+        match self.handle.take().map(JoinHandle::join) {
+            Some(Ok(finished)) => finished,
+            _ => Finished::default(),
+        }
+    }
+}
+
+impl Iterator for Solutions {
+    type Item = Calculation;
+
+    fn next(&mut self) -> Option<Calculation> {
+        while let Ok(solution) = self.rx.recv() {
+            if !self.raw.insert(solution.clone()) {
+                continue;
+            }
+            if !self.keep_equivalent && !self.seen.insert(solution.normalize()) {
+                continue;
+            }
+
+            return Some(solution);
+        }
+
+        None
+    }
+}
+
+impl Drop for Solutions {
+    fn drop(&mut self) {
+        // nobody waits for the rest of the search
+
+        if let Some(handle) = self.handle.take() {
+            self.cancel.cancel();
+            let _ = handle.join();
+        }
+    }
+}
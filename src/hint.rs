@@ -0,0 +1,37 @@
+use crate::{calculation::Calculation, difficulty::DifficultyModel, value::Rational};
+
+/// Hints towards `solution`, each revealing more than the one before:
+/// the operation of the final step, the value of one intermediate result,
+/// the calculation of that result and finally the whole solution. The
+/// revealed result is the operand that `model` rates harder, dice are not
+/// revealed as they show their value anyway.
+pub fn hints(solution: &Calculation, model: &impl DifficultyModel) -> Vec<String> {
+    let (op, parts) = match solution {
+        Calculation::Binary(op, a, b) => (op, vec![a.as_ref(), b.as_ref()]),
+        Calculation::Unary(op, a) => (op, vec![a.as_ref()]),
+        Calculation::Cube(..) => return vec![format!("Die Lösung ist {}", solution)],
+    };
+
+    let mut hints = vec![format!(
+        "Der letzte Rechenschritt ist eine {} ({})",
+        op.description(),
+        op.symbol()
+    )];
+
+    // a die shows its value anyway, of the calculated operands the harder
+    // one gives away more, the first one if they are rated the same
+    let part = parts
+        .into_iter()
+        .rev()
+        .filter(|part| !matches!(part, Calculation::Cube(..)))
+        .max_by_key(|part| model.score(part));
+    if let Some(part) = part {
+        if let Some(value) = part.evaluate::<Rational>() {
+            hints.push(format!("Ein Zwischenergebnis ist {}", value));
+            hints.push(format!("Ein Teil der Lösung ist {} = {}", part, value));
+        }
+    }
+    hints.push(format!("Die Lösung ist {}", solution));
+
+    hints
+}
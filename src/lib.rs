@@ -1,4 +1,18 @@
+pub mod arena;
+pub mod calculation;
+pub mod daily;
+pub mod die;
+pub mod difficulty;
 pub mod fastgame;
 pub mod game;
+pub mod hint;
+pub mod operator;
 pub mod output;
+pub mod parser;
+pub mod reach;
+mod results;
+pub mod rules;
 pub mod solver;
+pub mod subsets;
+pub mod table;
+pub mod value;
@@ -0,0 +1,308 @@
+use anyhow::{anyhow, Error};
+
+use crate::{rules::Rules, value::Value};
+
+/// Operation that combines intermediate results.
+///
+/// Which operators are allowed is part of the [`Rules`], the solvers, the
+/// display and the score of a [`crate::calculation::Calculation`] all look
+/// up their behaviour here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    /// `a ^ b` with a non-negative integer exponent.
+    Pow,
+    /// Remainder of the integer division `a % b`.
+    Mod,
+    /// Writes the digits of two non-negative integers next to each other,
+    /// `3 || 40 = 340`.
+    Concat,
+    /// Exact integer square root, `√49 = 7`.
+    Sqrt,
+    /// `n!` for a non-negative integer `n`.
+    Factorial,
+}
+
+impl Operator {
+    /// The operators of the classic game.
+    pub const BASIC: [Operator; 4] = [Operator::Add, Operator::Sub, Operator::Mul, Operator::Div];
+
+    pub const ALL: [Operator; 9] = [
+        Operator::Add,
+        Operator::Sub,
+        Operator::Mul,
+        Operator::Div,
+        Operator::Pow,
+        Operator::Mod,
+        Operator::Concat,
+        Operator::Sqrt,
+        Operator::Factorial,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Operator::Add => "add",
+            Operator::Sub => "sub",
+            Operator::Mul => "mul",
+            Operator::Div => "div",
+            Operator::Pow => "pow",
+            Operator::Mod => "mod",
+            Operator::Concat => "concat",
+            Operator::Sqrt => "sqrt",
+            Operator::Factorial => "fact",
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Operator::Add => "+",
+            Operator::Sub => "-",
+            Operator::Mul => "*",
+            Operator::Div => "/",
+            Operator::Pow => "^",
+            Operator::Mod => "%",
+            Operator::Concat => "||",
+            Operator::Sqrt => "√",
+            Operator::Factorial => "!",
+        }
+    }
+
+    /// German name of the operation, as shown in hints.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Operator::Add => "Addition",
+            Operator::Sub => "Subtraktion",
+            Operator::Mul => "Multiplikation",
+            Operator::Div => "Division",
+            Operator::Pow => "Potenz",
+            Operator::Mod => "Modulo",
+            Operator::Concat => "Verkettung",
+            Operator::Sqrt => "Wurzel",
+            Operator::Factorial => "Fakultät",
+        }
+    }
+
+    /// Difficulty added to a calculation by one use of the operator.
+    pub fn weight(&self) -> u32 {
+        match self {
+            Operator::Add => 20,
+            Operator::Sub => 21,
+            Operator::Mul => 30,
+            Operator::Div => 34,
+            Operator::Pow => 40,
+            Operator::Mod => 36,
+            Operator::Concat => 16,
+            Operator::Sqrt => 32,
+            Operator::Factorial => 32,
+        }
+    }
+
+    /// Whether the operator takes a single operand.
+    pub fn is_unary(&self) -> bool {
+        matches!(self, Operator::Sqrt | Operator::Factorial)
+    }
+
+    pub fn is_commutative(&self) -> bool {
+        matches!(self, Operator::Add | Operator::Mul)
+    }
+
+    /// Applies a binary operator, if the result is defined and representable.
+    pub fn apply<V: Value>(&self, a: V, b: V) -> Option<V> {
+        match self {
+            Operator::Add => a.checked_add(b),
+            Operator::Sub => a.checked_sub(b),
+            Operator::Mul => a.checked_mul(b),
+            Operator::Div => a.checked_div(b),
+            Operator::Pow => pow(a, b.to_int()?),
+            Operator::Mod => a.checked_rem(b),
+            Operator::Concat => {
+                let (a, b) = (a.to_int()?, b.to_int()?);
+                let shift = 10u64.checked_pow(b.checked_ilog10().unwrap_or(0) + 1)?;
+                V::from_int(a.checked_mul(shift)?.checked_add(b)?)
+            }
+            Operator::Sqrt | Operator::Factorial => None,
+        }
+    }
+
+    /// Applies a unary operator, if the result is defined and representable.
+    pub fn apply_unary<V: Value>(&self, a: V) -> Option<V> {
+        match self {
+            Operator::Sqrt => {
+                let a = a.to_int()?;
+                let root = a.isqrt();
+                if root * root == a {
+                    V::from_int(root)
+                } else {
+                    None
+                }
+            }
+            Operator::Factorial => {
+                let n = a.to_int()?;
+                (1..=n)
+                    .try_fold(1u64, |acc, i| acc.checked_mul(i))
+                    .and_then(V::from_int)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether `a op b` only repeats a calculation the solvers find in a
+    /// simpler form anyway.
+    fn is_redundant<V: Value>(&self, rules: &Rules, a: V, b: V) -> bool {
+        match self {
+            // subtracting a negative value or multiplying and dividing two of
+            // them only repeats the calculation with their negations
+            Operator::Sub => b.is_negative(),
+            Operator::Mul => a.is_negative() && b.is_negative(),
+            // dividing two multiples of ten repeats a division with smaller
+            // multipliers, if those are allowed
+            Operator::Div => {
+                (a.is_negative() && b.is_negative())
+                    || (a.is_round() && b.is_round() && rules.has_all_powers_of_ten())
+            }
+            _ => false,
+        }
+    }
+}
+
+/// `base ^ exp` by repeated multiplication.
+fn pow<V: Value>(base: V, exp: u64) -> Option<V> {
+    let one = V::from_int(1)?;
+    if base == one || exp == 0 {
+        return Some(one);
+    }
+
+    // anything larger overflows for every base but 0 and 1
+    if exp > u64::BITS as u64 {
+        return if base == V::from_int(0)? {
+            Some(base)
+        } else {
+            None
+        };
+    }
+
+    (0..exp).try_fold(one, |acc, _| acc.checked_mul(base))
+}
+
+/// Every value the binary operators of `rules` compute from `a` and `b`,
+/// together with the operator and whether the operands were swapped.
+///
+/// Commutative operators only yield the order with the larger operand first.
+pub(crate) fn combinations<V: Value>(
+    rules: &Rules,
+    a: V,
+    b: V,
+) -> impl Iterator<Item = (V, Operator, bool)> + '_ {
+    rules
+        .operators
+        .iter()
+        .filter(|op| !op.is_unary())
+        .flat_map(move |&op| {
+            let ordered = if op.is_commutative() && a < b {
+                [(b, a, true), (a, b, false)]
+            } else {
+                [(a, b, false), (b, a, true)]
+            };
+            let orders = if op.is_commutative() { 1 } else { 2 };
+
+            ordered
+                .into_iter()
+                .take(orders)
+                .filter(move |&(x, y, _)| !op.is_redundant(rules, x, y))
+                .filter_map(move |(x, y, swapped)| Some((op.apply(x, y)?, op, swapped)))
+        })
+        .filter(|&(value, _, _)| rules.allows(value))
+}
+
+/// Every value the unary operators of `rules` compute from `a`, leaving out
+/// those that do not change it, like `√1` or `2!`.
+pub(crate) fn unary_combinations<V: Value>(
+    rules: &Rules,
+    a: V,
+) -> impl Iterator<Item = (V, Operator)> + '_ {
+    rules
+        .operators
+        .iter()
+        .filter(|op| op.is_unary())
+        .filter_map(move |&op| Some((op.apply_unary(a)?, op)))
+        .filter(move |&(value, _)| value != a && rules.allows(value))
+}
+
+/// The target `n` and every value a unary operator of `rules` turns into
+/// `n`, like `49` for `√49 = 7`. A final step that hits one of them solves
+/// the game.
+pub(crate) fn final_targets<V: Value>(rules: &Rules, n: V) -> Vec<V> {
+    let mut targets = vec![n];
+    for op in rules.operators.iter().filter(|op| op.is_unary()) {
+        let preimages = match op {
+            Operator::Sqrt => n.checked_mul(n).into_iter().collect(),
+            // 21! does not fit into a u64
+            Operator::Factorial => (0..=20).filter_map(V::from_int).collect(),
+            _ => Vec::new(),
+        };
+        targets.extend(
+            preimages
+                .into_iter()
+                .filter(|&v| unary_combinations(rules, v).any(|(result, _)| result == n)),
+        );
+    }
+
+    targets.sort();
+    targets.dedup();
+    targets
+}
+
+/// Every `b` for which a binary operator of `rules` may combine `a` and `b`
+/// to `target`, in either order. `None` if that can not be narrowed down,
+/// as for `0 * b = 0` or an operator without an inverse, then every `b` has
+/// to be tried.
+///
+/// Whether `a` and `b` actually give `target` is left to [`combinations`].
+pub(crate) fn partners<V: Value>(rules: &Rules, a: V, target: V) -> Option<Vec<V>> {
+    let zero = V::from_int(0)?;
+    let mut partners = Vec::new();
+    for op in rules.operators.iter().filter(|op| !op.is_unary()) {
+        match op {
+            Operator::Add => partners.extend(target.checked_sub(a)),
+            Operator::Sub => partners.extend(
+                [a.checked_sub(target), target.checked_add(a)]
+                    .into_iter()
+                    .flatten(),
+            ),
+            Operator::Mul if a == zero && target == zero => return None,
+            Operator::Mul => partners.extend(target.checked_div(a)),
+            Operator::Div if a == zero && target == zero => return None,
+            Operator::Div => partners.extend(
+                [a.checked_div(target), target.checked_mul(a)]
+                    .into_iter()
+                    .flatten(),
+            ),
+            _ => return None,
+        }
+    }
+
+    partners.sort();
+    partners.dedup();
+    Some(partners)
+}
+
+impl std::str::FromStr for Operator {
+    type Err = Error;
+
+    /// Parses an operator from its name or symbol.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Operator::ALL
+            .into_iter()
+            .find(|op| op.name() == s || op.symbol() == s)
+            .ok_or_else(|| anyhow!("Unbekannter Operator: {}", s))
+    }
+}
+
+impl std::fmt::Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
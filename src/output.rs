@@ -12,12 +12,11 @@ impl Output {
         lines[5] += "               /_____\\__,_|_| |_|_| (_)    ";
 
         let d = Self::digits_string(num);
-        let mut s = d.split("\n");
-        for i in 0..6 {
-            lines[i] += s.next().unwrap();
+        for (line, digits) in lines.iter_mut().zip(d.split("\n")) {
+            *line += digits;
         }
 
-        return lines.join("\n");
+        lines.join("\n")
     }
 
     pub fn digits_string(num: u64) -> String {
@@ -119,7 +118,7 @@ impl Output {
             }
         }
 
-        return lines.join("\n");
+        lines.join("\n")
     }
     pub fn dices_string(dices: &[u64]) -> String {
         let mut lines = vec![String::from("     "); 6];
@@ -133,12 +132,11 @@ impl Output {
 
         for dice in dices {
             let d = Self::digits_string(*dice);
-            let mut s = d.split("\n");
-            for i in 0..6 {
-                lines[i] += s.next().unwrap();
+            for (line, digits) in lines.iter_mut().zip(d.split("\n")) {
+                *line += digits;
             }
         }
 
-        return lines.join("\n");
+        lines.join("\n")
     }
 }
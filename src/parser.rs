@@ -0,0 +1,312 @@
+use anyhow::{anyhow, Result};
+
+use crate::{
+    calculation::Calculation,
+    operator::Operator,
+    rules::Rules,
+    value::{Rational, Value},
+};
+
+/// Parses a solution typed by a player, like `((60 / 6) + (40 * 3))`.
+///
+/// Multiplication may be written as `*`, `x` or `×`, division as `/`, `:` or
+/// `÷`. Without parentheses `^` binds stronger than `*`, `/` and `%`, which
+/// bind stronger than `+` and `-`. Every number is mapped to one of `dices`
+/// times one of the multipliers of `rules`, so that no die is used twice.
+/// If several mappings work, the smallest multipliers are preferred.
+///
+/// Whether all dice are used and the target is reached is left to
+/// [`Calculation::verify`].
+pub fn parse(input: &str, dices: &[u64], rules: &Rules) -> Result<Calculation> {
+    let mut parser = Parser {
+        chars: input.chars().collect(),
+        pos: 0,
+        dices,
+        rules,
+        numbers: Vec::new(),
+    };
+
+    let calc = parser.expression()?;
+    parser.skip_whitespace();
+    if let Some(c) = parser.peek() {
+        return Err(anyhow!(
+            "Unerwartetes Zeichen '{}' an Stelle {}",
+            c,
+            parser.pos + 1
+        ));
+    }
+
+    let dice = assign(&parser.numbers, dices)?;
+    Ok(with_dice(calc, &dice))
+}
+
+/// A die for each number, so that no die is used twice. The numbers are
+/// placed one after another, the first one that does not fit anymore is
+/// reported.
+fn assign(numbers: &[Vec<usize>], dices: &[u64]) -> Result<Vec<usize>> {
+    let mut dice = Vec::new();
+    for len in 1..=numbers.len() {
+        dice.clear();
+        let mut used = vec![false; dices.len()];
+        if !place(&numbers[..len], &mut used, &mut dice) {
+            let i = numbers[len - 1][0];
+            return Err(anyhow!(
+                "Würfel{} ({}) wird doppelt benutzt",
+                i + 1,
+                dices[i]
+            ));
+        }
+    }
+
+    Ok(dice)
+}
+
+/// Places the remaining `numbers` on unused dice, trying the preferred
+/// candidates of each number first.
+fn place(numbers: &[Vec<usize>], used: &mut [bool], dice: &mut Vec<usize>) -> bool {
+    let Some((candidates, rest)) = numbers.split_first() else {
+        return true;
+    };
+
+    for &i in candidates {
+        if used[i] {
+            continue;
+        }
+        used[i] = true;
+        dice.push(i);
+        if place(rest, used, dice) {
+            return true;
+        }
+        used[i] = false;
+        dice.pop();
+    }
+
+    false
+}
+
+/// Replaces the position of every number in `calc` by its die.
+fn with_dice(calc: Calculation, dice: &[usize]) -> Calculation {
+    match calc {
+        Calculation::Binary(op, a, b) => {
+            Calculation::binary(op, with_dice(*a, dice), with_dice(*b, dice))
+        }
+        Calculation::Unary(op, a) => Calculation::unary(op, with_dice(*a, dice)),
+        Calculation::Cube(number, value) => Calculation::Cube(dice[number], value),
+    }
+}
+
+struct Parser<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    dices: &'a [u64],
+    rules: &'a Rules,
+
+    /// The dice each number read so far may stand for, preferred first.
+    numbers: Vec<Vec<usize>>,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    /// Consumes `token` after optional whitespace, if it comes next.
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        let len = token.chars().count();
+        let matches = self.chars.len() >= self.pos + len
+            && self.chars[self.pos..self.pos + len]
+                .iter()
+                .copied()
+                .eq(token.chars());
+        if matches {
+            self.pos += len;
+        }
+        matches
+    }
+
+    /// Consumes the first of `operators` that comes next.
+    fn operator(&mut self, operators: &[(&str, Operator)]) -> Option<Operator> {
+        operators
+            .iter()
+            .find(|(token, _)| self.eat(token))
+            .map(|(_, op)| *op)
+    }
+
+    /// `term (('+' | '-') term)*`
+    fn expression(&mut self) -> Result<Calculation> {
+        let mut calc = self.term()?;
+        while let Some(op) = self.operator(&[("+", Operator::Add), ("-", Operator::Sub)]) {
+            calc = Calculation::binary(op, calc, self.term()?);
+        }
+
+        Ok(calc)
+    }
+
+    /// `power (('*' | '/' | '%') power)*`
+    fn term(&mut self) -> Result<Calculation> {
+        let operators = [
+            ("*", Operator::Mul),
+            ("x", Operator::Mul),
+            ("×", Operator::Mul),
+            ("/", Operator::Div),
+            (":", Operator::Div),
+            ("÷", Operator::Div),
+            ("%", Operator::Mod),
+        ];
+
+        let mut calc = self.power()?;
+        while let Some(op) = self.operator(&operators) {
+            let operand = self.power()?;
+            if op == Operator::Div {
+                self.check_division(&calc, &operand)?;
+            }
+            calc = Calculation::binary(op, calc, operand);
+        }
+
+        Ok(calc)
+    }
+
+    /// `concat ('^' power)?`, evaluated from the right.
+    fn power(&mut self) -> Result<Calculation> {
+        let base = self.concat()?;
+        if self.eat("^") {
+            return Ok(Calculation::binary(Operator::Pow, base, self.power()?));
+        }
+
+        Ok(base)
+    }
+
+    /// `postfix ('||' postfix)*`
+    fn concat(&mut self) -> Result<Calculation> {
+        let mut calc = self.postfix()?;
+        while self.eat("||") {
+            calc = Calculation::binary(Operator::Concat, calc, self.postfix()?);
+        }
+
+        Ok(calc)
+    }
+
+    /// `prefix '!'*`
+    fn postfix(&mut self) -> Result<Calculation> {
+        let mut calc = self.prefix()?;
+        while self.eat("!") {
+            calc = Calculation::unary(Operator::Factorial, calc);
+        }
+
+        Ok(calc)
+    }
+
+    /// `'√' prefix | '(' expression ')' | number`
+    fn prefix(&mut self) -> Result<Calculation> {
+        if self.eat("√") {
+            return Ok(Calculation::unary(Operator::Sqrt, self.prefix()?));
+        }
+
+        if self.eat("(") {
+            let calc = self.expression()?;
+            if !self.eat(")") {
+                return Err(self.unexpected("')'"));
+            }
+            return Ok(calc);
+        }
+
+        self.number()
+    }
+
+    fn number(&mut self) -> Result<Calculation> {
+        self.skip_whitespace();
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(self.unexpected("eine Zahl"));
+        }
+
+        let digits = self.chars[start..self.pos].iter().collect::<String>();
+        let value = digits
+            .parse::<u64>()
+            .map_err(|_| anyhow!("Zahl {} ist zu groß", digits))?;
+
+        self.cube(value)
+    }
+
+    /// Remembers the dice `value` may stand for. The cube refers to the
+    /// number until the dice are assigned, see [`assign`].
+    fn cube(&mut self, value: u64) -> Result<Calculation> {
+        let multiplier = |die: u64| {
+            self.rules
+                .multipliers
+                .iter()
+                .copied()
+                .find(|&m| die.checked_mul(m) == Some(value))
+        };
+
+        // the dice with the smallest fitting multiplier first
+        let mut candidates = (0..self.dices.len())
+            .filter_map(|i| Some((multiplier(self.dices[i])?, i)))
+            .collect::<Vec<_>>();
+        candidates.sort();
+        if !candidates.is_empty() {
+            self.numbers
+                .push(candidates.into_iter().map(|(_, i)| i).collect());
+            return Ok(Calculation::Cube(self.numbers.len() - 1, value));
+        }
+
+        // the right die, but times a factor that is not allowed
+        let power_of_ten = |factor: u64| factor > 0 && 10u64.pow(factor.ilog10()) == factor;
+        let die = self
+            .dices
+            .iter()
+            .find(|&&die| die != 0 && value.is_multiple_of(die) && power_of_ten(value / die));
+        if let Some(die) = die {
+            return Err(anyhow!(
+                "Faktor {} ist für {} nicht erlaubt",
+                value / die,
+                die
+            ));
+        }
+
+        Err(anyhow!("Zu {} gibt es keinen Würfel", value))
+    }
+
+    /// Rejects divisions that do not have an integer result, unless the
+    /// rules allow fractions.
+    fn check_division(&self, dividend: &Calculation, divisor: &Calculation) -> Result<()> {
+        let (Some(a), Some(b)) = (
+            dividend.evaluate::<Rational>(),
+            divisor.evaluate::<Rational>(),
+        ) else {
+            return Ok(());
+        };
+
+        match a.checked_div(b) {
+            None => Err(anyhow!("Division durch 0 in ({} / {})", dividend, divisor)),
+            Some(q) if q.denominator() != 1 && !self.rules.rational => Err(anyhow!(
+                "Division ({} / {}) geht nicht auf",
+                dividend,
+                divisor
+            )),
+            Some(_) => Ok(()),
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> anyhow::Error {
+        match self.peek() {
+            Some(c) => anyhow!(
+                "Erwartet {} statt '{}' an Stelle {}",
+                expected,
+                c,
+                self.pos + 1
+            ),
+            None => anyhow!("Erwartet {} am Ende der Eingabe", expected),
+        }
+    }
+}
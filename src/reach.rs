@@ -0,0 +1,175 @@
+use std::{collections::HashSet, ops::Range};
+
+use crate::{
+    operator::{combinations, unary_combinations},
+    rules::Rules,
+    solver::{CancelToken, Stop},
+    subsets::splits,
+    value::{Rational, Value},
+};
+
+/// Targets in a window, one bit each.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bitset {
+    pub range: Range<u64>,
+    words: Vec<u64>,
+}
+
+impl Bitset {
+    /// An empty set for the targets in `range`.
+    pub fn new(range: Range<u64>) -> Self {
+        let words = (range.end.saturating_sub(range.start) as usize).div_ceil(64);
+
+        Self {
+            range,
+            words: vec![0; words],
+        }
+    }
+
+    /// Adds `value`, values outside of the range are ignored.
+    pub fn insert(&mut self, value: u64) {
+        if self.range.contains(&value) {
+            let bit = (value - self.range.start) as usize;
+            self.words[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+
+    pub fn contains(&self, value: u64) -> bool {
+        if !self.range.contains(&value) {
+            return false;
+        }
+        let bit = (value - self.range.start) as usize;
+        self.words[bit / 64] & (1 << (bit % 64)) != 0
+    }
+
+    /// The values in the set, in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        self.range.clone().filter(|&value| self.contains(value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&word| word == 0)
+    }
+
+    /// The words of the set, bit `i` stands for `range.start + i`.
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+}
+
+/// Engine that only finds out which targets can be reached, not how.
+///
+/// Each subset of the dice keeps the values it reaches without their
+/// calculations, and the final step of all dice marks every hit of the
+/// window in one pass, instead of checking each target on its own.
+///
+/// Only the final step is a [`Bitset`]. The values of a proper subset are a
+/// sorted list, because intermediate results are not bounded by the window:
+/// `(600 * 400) / 300` passes through 240000, and with the rules they may
+/// also be negative or fractions. A bitset of the window would lose them.
+#[derive(Debug, Clone, Default)]
+pub struct Reach {
+    pub rules: Rules,
+}
+
+impl Reach {
+    pub fn new(rules: Rules) -> Self {
+        Self { rules }
+    }
+
+    /// Every target in `range` that can be reached with `dices`.
+    pub fn coverage(&self, range: Range<u64>, dices: &[u64]) -> Bitset {
+        coverage(&self.rules, range, dices, &Stop::new(&CancelToken::new()))
+    }
+}
+
+/// Like [`Reach::coverage`], but gives up as soon as `stop` says so. The
+/// targets found until then are returned.
+pub(crate) fn coverage(rules: &Rules, range: Range<u64>, dices: &[u64], stop: &Stop) -> Bitset {
+    match (rules.rational, rules.negative) {
+        (true, _) => coverage_with::<Rational>(rules, range, dices, stop),
+        (false, true) => coverage_with::<i64>(rules, range, dices, stop),
+        (false, false) => coverage_with::<u64>(rules, range, dices, stop),
+    }
+}
+
+fn coverage_with<V: Value>(rules: &Rules, range: Range<u64>, dices: &[u64], stop: &Stop) -> Bitset {
+    let mut coverage = Bitset::new(range);
+    if dices.is_empty() {
+        return coverage;
+    }
+
+    let full = (1usize << dices.len()) - 1;
+    let values = fill_values::<V>(dices, rules, stop);
+
+    let mut hit = |value: V| {
+        if let Some(value) = value.to_int() {
+            coverage.insert(value);
+        }
+    };
+    for (part1, part2) in splits(full) {
+        for &a in &values[part1] {
+            if stop.now() {
+                break;
+            }
+            for &b in &values[part2] {
+                for (value, _, _) in combinations(rules, a, b) {
+                    hit(value);
+                    // a unary operator may also be applied to the final
+                    // result
+                    for (result, _) in unary_combinations(rules, value) {
+                        hit(result);
+                    }
+                }
+            }
+        }
+    }
+
+    coverage
+}
+
+/// Values reached by every proper subset of `dices`, indexed by the bitmask
+/// of the used dice. The entry of the full set is left empty, like the
+/// entries not reached before `stop`.
+fn fill_values<V: Value>(dices: &[u64], rules: &Rules, stop: &Stop) -> Vec<Vec<V>> {
+    let full = (1usize << dices.len()) - 1;
+    let mut values = vec![Vec::new(); full + 1];
+
+    for mask in 1..full {
+        if stop.now() {
+            break;
+        }
+        let mut set = HashSet::new();
+        if mask.count_ones() == 1 {
+            let die = dices[mask.trailing_zeros() as usize];
+            set.extend(rules.die_values(die).filter_map(V::from_int));
+        }
+        for (part1, part2) in splits(mask) {
+            for &a in &values[part1] {
+                for &b in &values[part2] {
+                    set.extend(combinations(rules, a, b).map(|(value, _, _)| value));
+                }
+            }
+        }
+
+        // unary operators are applied once, like in the solvers
+        let unary = set
+            .iter()
+            .flat_map(|&value| unary_combinations(rules, value).map(|(result, _)| result))
+            .collect::<Vec<_>>();
+        set.extend(unary);
+
+        let mut sorted = set.into_iter().collect::<Vec<_>>();
+        sorted.sort();
+        values[mask] = sorted;
+    }
+
+    values
+}
@@ -0,0 +1,131 @@
+use std::{collections::HashMap, hash::BuildHasher};
+
+use crate::{
+    arena::{Arena, Node, NodeId},
+    calculation::Calculation,
+    operator::{combinations, unary_combinations, Operator},
+    rules::Rules,
+    solver::{Closest, Stop},
+    value::Value,
+};
+
+/// The derivations a result map keeps for one value, as nodes of the arena.
+/// The game keeps a list of them, the fast game only one.
+pub(crate) trait Derivations {
+    fn of(id: NodeId) -> Self;
+
+    fn ids(&self) -> &[NodeId];
+
+    /// Adds a derivation, or replaces it if only one is kept.
+    fn add(&mut self, id: NodeId);
+}
+
+impl Derivations for Vec<NodeId> {
+    fn of(id: NodeId) -> Self {
+        vec![id]
+    }
+
+    fn ids(&self) -> &[NodeId] {
+        self
+    }
+
+    fn add(&mut self, id: NodeId) {
+        self.push(id);
+    }
+}
+
+impl Derivations for NodeId {
+    fn of(id: NodeId) -> Self {
+        id
+    }
+
+    fn ids(&self) -> &[NodeId] {
+        std::slice::from_ref(self)
+    }
+
+    fn add(&mut self, id: NodeId) {
+        *self = id;
+    }
+}
+
+/// Adds the results of the unary operators to `map`. They are applied once,
+/// so `√√x` or `(3!)!` are not found. Values that are already reachable
+/// otherwise are kept unless all solutions are requested.
+pub(crate) fn apply_unary<V: Value, T: Derivations, S: BuildHasher>(
+    map: &mut HashMap<V, T, S>,
+    rules: &Rules,
+    all: bool,
+    arena: &mut Arena,
+) {
+    let mut results = Vec::new();
+    for (value, derivations) in map.iter() {
+        for (result, op) in unary_combinations(rules, *value) {
+            if all || !map.contains_key(&result) {
+                for &id in derivations.ids() {
+                    results.push((result, Node::Unary(op, id)));
+                }
+            }
+        }
+    }
+
+    for (result, node) in results {
+        let id = arena.push(node);
+        match map.get_mut(&result) {
+            Some(derivations) => derivations.add(id),
+            None => {
+                map.insert(result, T::of(id));
+            }
+        }
+    }
+}
+
+/// Offers every combination of a result of `map1` and one of `map2` to
+/// `closest`, for when the target is not reached.
+pub(crate) fn check_for_closest<V: Value, T: Derivations, S: BuildHasher>(
+    map1: &HashMap<V, T, S>,
+    map2: &HashMap<V, T, S>,
+    rules: &Rules,
+    arena: &Arena,
+    n: V,
+    closest: &mut Closest,
+    stop: &Stop,
+) {
+    let num = n.to_int().unwrap_or_default();
+
+    for (r1, derivations1) in map1 {
+        if stop.now() {
+            return;
+        }
+        let id1 = derivations1.ids()[0];
+        for (r2, derivations2) in map2 {
+            let id2 = derivations2.ids()[0];
+            for (value, op, swapped) in combinations(rules, *r1, *r2) {
+                let calc = || binary(arena, op, swapped, id1, id2);
+                if let Some(value) = value.to_int() {
+                    closest.offer(num, value, calc);
+                }
+                for (result, unary_op) in unary_combinations(rules, value) {
+                    if let Some(result) = result.to_int() {
+                        closest.offer(num, result, || Calculation::unary(unary_op, calc()));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The calculation of the nodes `id1` and `id2` combined by `op`.
+pub(crate) fn binary(
+    arena: &Arena,
+    op: Operator,
+    swapped: bool,
+    id1: NodeId,
+    id2: NodeId,
+) -> Calculation {
+    let (calc1, calc2) = (arena.calculation(id1), arena.calculation(id2));
+    if swapped {
+        Calculation::binary(op, calc2, calc1)
+    } else {
+        Calculation::binary(op, calc1, calc2)
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{die::Die, operator::Operator, value::Value};
+
+/// Variant of the game the solvers play by.
+///
+/// The default is the classic game as described in the README.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rules {
+    /// Allow intermediate results to be fractions, e.g. `((3 / 4) * 400)`.
+    /// Only the final result has to be the integer target.
+    pub rational: bool,
+
+    /// Allow intermediate results to be negative, e.g. `((3 - 40) * 2)`.
+    pub negative: bool,
+
+    /// Operators that may be used to combine the dice.
+    pub operators: Vec<Operator>,
+
+    /// Factors a die may be multiplied with, e.g. `[1, 10, 100]` allows a
+    /// 4 to be used as 4, 40 or 400.
+    pub multipliers: Vec<u64>,
+
+    /// The die every cube of the game is rolled with.
+    pub die: Die,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            rational: false,
+            negative: false,
+            operators: Operator::BASIC.to_vec(),
+            multipliers: Rules::powers_of_ten(7),
+            die: Die::d6(),
+        }
+    }
+}
+
+impl Rules {
+    /// The multipliers `1, 10, ..., 10^max_exponent`.
+    pub fn powers_of_ten(max_exponent: u32) -> Vec<u64> {
+        (0..=max_exponent)
+            .filter_map(|e| 10u64.checked_pow(e))
+            .collect()
+    }
+
+    /// Whether every power of ten up to the largest multiplier is allowed,
+    /// so a die used as a multiple of ten may also be used a tenth as large.
+    pub fn has_all_powers_of_ten(&self) -> bool {
+        let Some(&largest) = self.multipliers.iter().max() else {
+            return false;
+        };
+
+        std::iter::successors(Some(1u64), |p| p.checked_mul(10))
+            .take_while(|&p| p <= largest)
+            .all(|p| self.multipliers.contains(&p))
+    }
+
+    /// Every value die `c` may be used as.
+    pub fn die_values(&self, c: u64) -> impl Iterator<Item = u64> + '_ {
+        self.multipliers
+            .iter()
+            .filter_map(move |m| c.checked_mul(*m))
+    }
+
+    /// The command line options that select these rules, only those that
+    /// differ from the default rules.
+    pub fn options(&self) -> Vec<String> {
+        let default = Rules::default();
+        let mut options = Vec::new();
+
+        if self.rational {
+            options.push("--rational".to_string());
+        }
+        if self.negative {
+            options.push("--negative".to_string());
+        }
+        if self.operators != default.operators {
+            let names = self
+                .operators
+                .iter()
+                .map(|op| op.name())
+                .collect::<Vec<_>>();
+            options.extend(["--ops".to_string(), names.join(",")]);
+        }
+        if self.multipliers != default.multipliers {
+            let factors = self
+                .multipliers
+                .iter()
+                .map(|m| m.to_string())
+                .collect::<Vec<_>>();
+            options.extend(["--multipliers".to_string(), factors.join(",")]);
+        }
+        if self.die != default.die {
+            // without the brackets, which a shell might expand
+            let die = self.die.to_string().replace(['[', ']'], "");
+            options.extend(["--die".to_string(), die]);
+        }
+
+        options
+    }
+
+    /// Whether `value` may be used as an intermediate result.
+    pub fn allows<V: Value>(&self, value: V) -> bool {
+        self.negative || !value.is_negative()
+    }
+}
@@ -1,21 +1,271 @@
-use std::ops::Range;
+use std::{
+    cell::Cell,
+    collections::{BTreeSet, HashMap, HashSet},
+    hash::BuildHasher,
+    ops::Range,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
 use rayon::prelude::*;
 
-use crate::game::{DiceAmount, Game};
+use crate::{
+    calculation::Calculation,
+    game::Game,
+    operator::{final_targets, partners},
+    rules::Rules,
+    value::Value,
+};
 
-pub struct Solver {
+/// Common interface of the solving engines in [`crate::game`] and
+/// [`crate::fastgame`], so tooling can swap one for the other.
+pub trait Solver {
+    /// All solutions found for reaching `num` with `dices`.
+    fn solve_for(&mut self, num: u64, dices: &[u64]) -> Vec<Calculation>;
+
+    /// All values in `range` that can be reached with `dices`, in ascending
+    /// order.
+    fn reachable(&mut self, range: Range<u64>, dices: &[u64]) -> Vec<u64>;
+
+    /// Whether `num` can be reached with `dices`.
+    fn is_reachable(&mut self, num: u64, dices: &[u64]) -> bool {
+        match num.checked_add(1) {
+            Some(end) => !self.reachable(num..end, dices).is_empty(),
+            // no range ends after u64::MAX
+            None => !self.solve_for(num, dices).is_empty(),
+        }
+    }
+}
+
+/// Stops a search early, on request or once a deadline has passed. Clones
+/// share the request, so a search can be cancelled from another thread.
+///
+/// A stopped search keeps what it found so far and marks itself as
+/// incomplete.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+
+    /// The requests of the tokens this one was derived from.
+    parents: Vec<Arc<AtomicBool>>,
+    deadline: Option<Instant>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A token that cancels the search after `timeout` from now.
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self {
+            deadline: Instant::now().checked_add(timeout),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_deadline(deadline: Instant) -> Self {
+        Self {
+            deadline: Some(deadline),
+            ..Self::default()
+        }
+    }
+
+    /// A token that is cancelled together with this one, but can also be
+    /// cancelled on its own without cancelling this one.
+    pub fn child(&self) -> Self {
+        let mut parents = self.parents.clone();
+        parents.push(self.cancelled.clone());
+
+        Self {
+            cancelled: Arc::default(),
+            parents,
+            deadline: self.deadline,
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+            || self
+                .parents
+                .iter()
+                .any(|cancelled| cancelled.load(Ordering::Relaxed))
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+/// Polls a [`CancelToken`] during one search and remembers whether it
+/// stopped the search.
+pub(crate) struct Stop<'a> {
+    token: &'a CancelToken,
+    stopped: Cell<bool>,
+}
+
+impl<'a> Stop<'a> {
+    pub(crate) fn new(token: &'a CancelToken) -> Self {
+        Self {
+            token,
+            stopped: Cell::new(false),
+        }
+    }
+
+    /// Whether the search has to stop now.
+    pub(crate) fn now(&self) -> bool {
+        if !self.stopped.get() && self.token.is_cancelled() {
+            self.stopped.set(true);
+        }
+        self.stopped.get()
+    }
+
+    /// Whether the search was stopped before it was done.
+    pub(crate) fn stopped(&self) -> bool {
+        self.stopped.get()
+    }
+}
+
+/// Values in `range` on whose reachability with `dices` the two solvers
+/// disagree, in ascending order.
+pub fn cross_check(
+    first: &mut impl Solver,
+    second: &mut impl Solver,
+    range: Range<u64>,
+    dices: &[u64],
+) -> Vec<u64> {
+    let first = BTreeSet::from_iter(first.reachable(range.clone(), dices));
+    let second = BTreeSet::from_iter(second.reachable(range, dices));
+
+    first.symmetric_difference(&second).copied().collect()
+}
+
+/// The pairs of results of `map1` and `map2` that may give `n` in the final
+/// step. Instead of trying every pair, the partners of each result of
+/// `map1` are looked up in `map2`, see [`partners`].
+pub(crate) fn final_pairs<'a, V: Value, T, S: BuildHasher>(
+    map1: &'a HashMap<V, T, S>,
+    map2: &'a HashMap<V, T, S>,
+    rules: &'a Rules,
+    n: V,
+) -> impl Iterator<Item = ((&'a V, &'a T), (&'a V, &'a T))> + 'a {
+    let targets = final_targets(rules, n);
+
+    map1.iter().flat_map(move |entry| {
+        let candidates = targets
+            .iter()
+            .map(|&target| partners(rules, *entry.0, target))
+            .collect::<Option<Vec<_>>>();
+        let mut candidates = match candidates {
+            Some(candidates) => candidates
+                .into_iter()
+                .flatten()
+                .filter_map(|r2| map2.get_key_value(&r2))
+                .collect::<Vec<_>>(),
+            None => map2.iter().collect(),
+        };
+        candidates.sort_by_key(|(r2, _)| **r2);
+        candidates.dedup_by_key(|(r2, _)| **r2);
+
+        candidates
+            .into_iter()
+            .map(move |candidate| (entry, candidate))
+    })
+}
+
+/// Results nearest to a target that is not reached exactly, like in the
+/// scoring of Countdown.
+#[derive(Debug, Clone, Default)]
+pub struct Closest {
+    /// Distance of the kept results to the target, `None` until the first
+    /// result is offered.
+    pub distance: Option<u64>,
+
+    /// Reached values together with their calculation.
+    pub results: Vec<(u64, Calculation)>,
+}
+
+impl Closest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps the calculation of `value` if it is at least as close to `num`
+    /// as the results so far. Farther results and repeated calculations are
+    /// dropped.
+    pub fn offer(&mut self, num: u64, value: u64, calc: impl FnOnce() -> Calculation) {
+        let distance = value.abs_diff(num);
+        match self.distance {
+            Some(best) if distance > best => return,
+            Some(best) if distance == best => {}
+            _ => {
+                self.distance = Some(distance);
+                self.results.clear();
+            }
+        }
+
+        let result = (value, calc());
+        if !self.results.contains(&result) {
+            self.results.push(result);
+        }
+    }
+
+    /// Keeps the first of every group of results with the same canonical
+    /// form, like [`crate::calculation::canonical_solutions`].
+    pub fn keep_canonical(&mut self) {
+        let mut seen = HashSet::new();
+        self.results
+            .retain(|(_, calc)| seen.insert(calc.normalize()));
+    }
+
+    pub fn clear(&mut self) {
+        self.distance = None;
+        self.results.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    pub fn print(&self) {
+        let Some(distance) = self.distance else {
+            println!("\nKeine Zahl erreichbar\n");
+            return;
+        };
+
+        let mut results = self.results.iter().collect::<Vec<_>>();
+        results.sort_by_key(|(value, calc)| (*value, calc.score()));
+
+        println!("\nNächste erreichbare Ergebnisse (Abstand {}):", distance);
+        for (value, calc) in results {
+            println!("\t{} = {}", calc, value);
+        }
+        println!("\n");
+    }
+}
+
+/// Finds the largest gaps between reachable targets of four dice.
+///
+/// This was `solver::Solver` before the name went to the [`Solver`] trait.
+#[derive(Default)]
+pub struct DistanceFinder {
     pub best_distance: Vec<Vec<u16>>,
 }
 
-impl Solver {
+impl DistanceFinder {
     pub fn new() -> Self {
-        Solver {
+        DistanceFinder {
             best_distance: Vec::with_capacity(1296),
         }
     }
 
-    fn find_4_distances(&mut self) -> Vec<Vec<u16>> {
+    pub fn find_4_distances(&mut self) -> Vec<Vec<u16>> {
         let mut distances: Vec<Vec<u16>> = Vec::with_capacity(1296);
         for _ in 0..1296 {
             distances.push(Vec::new());
@@ -30,17 +280,11 @@ impl Solver {
 
                 for value in values {
                     let mut game = Game::of(
-                        DiceAmount::Four,
                         value as u64,
-                        [
-                            permutation[0] as u64,
-                            permutation[1] as u64,
-                            permutation[2] as u64,
-                            permutation[3] as u64,
-                        ],
+                        permutation.iter().map(|&d| d as u64).collect(),
                     );
                     game.solve();
-                    if game.solutions.len() > 0 {
+                    if !game.solutions.is_empty() {
                         solutions.push(value);
                     }
                 }
@@ -56,8 +300,8 @@ impl Solver {
 fn permutation_from_index(mut index: usize) -> [u8; 4] {
     let mut permutation = [0u8; 4];
 
-    for i in 0..4 {
-        permutation[i] = (index % 6) as u8 + 1; // 1 to 6
+    for die in permutation.iter_mut() {
+        *die = (index % 6) as u8 + 1; // 1 to 6
         index /= 6;
     }
 
@@ -0,0 +1,19 @@
+/// All ways to split the dice in `mask` into two non-empty disjoint subsets.
+///
+/// Every unordered pair is yielded once, as combining two result maps is
+/// symmetric.
+pub fn splits(mask: usize) -> impl Iterator<Item = (usize, usize)> {
+    let mut sub = mask;
+
+    std::iter::from_fn(move || loop {
+        sub = sub.wrapping_sub(1) & mask;
+        if sub == 0 {
+            return None;
+        }
+
+        let rest = mask ^ sub;
+        if sub > rest {
+            return Some((sub, rest));
+        }
+    })
+}
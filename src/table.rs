@@ -0,0 +1,302 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    ops::Range,
+    path::Path,
+};
+
+use anyhow::{anyhow, Result};
+use itertools::Itertools;
+use rayon::prelude::*;
+
+use crate::{die::Die, operator::Operator, reach::Reach, rules::Rules};
+
+const MAGIC: &[u8; 4] = b"NUMS";
+const VERSION: u8 = 1;
+
+/// Limits of a table that is read, so that a corrupt header can not make
+/// reading enumerate or allocate without end.
+const MAX_BITMAPS: u64 = 1 << 20;
+const MAX_BYTES: u64 = 1 << 30;
+const MAX_NUMBERS: u32 = 1 << 16;
+
+/// Precomputed reachable targets of every multiset of dice, so checking
+/// whether a game is solvable does not need a solve.
+///
+/// Only the sorted dice matter for reachability, so four d6 need 126
+/// bitmaps and three d6 need 56. The file starts with a header of the rules
+/// and the covered targets, a table is only used for the rules it was built
+/// with, see [`Table::fits`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    pub num_dices: usize,
+    pub rules: Rules,
+
+    /// Targets the bitmaps cover.
+    pub range: Range<u64>,
+
+    /// Position of the bitmap of each multiset of dice, sorted ascending.
+    index: HashMap<Vec<u64>, usize>,
+
+    /// Words of one bitmap.
+    words: usize,
+
+    /// The bitmaps of all multisets one after another, bit `i` stands for
+    /// the target `range.start + i`.
+    bits: Vec<u64>,
+}
+
+impl Table {
+    /// Finds the reachable targets in `range` of every multiset of
+    /// `num_dices` dice of the die of `rules`, see [`Reach`].
+    pub fn build(num_dices: usize, rules: Rules, range: Range<u64>) -> Self {
+        let words = (range.end.saturating_sub(range.start) as usize).div_ceil(64);
+        let multisets = multisets(num_dices, &rules.die);
+
+        let reach = Reach::new(rules.clone());
+        let bitmaps = multisets
+            .par_iter()
+            .map(|dices| reach.coverage(range.clone(), dices).words().to_vec())
+            .collect::<Vec<_>>();
+
+        Self {
+            num_dices,
+            rules,
+            range,
+            index: index(multisets),
+            words,
+            bits: bitmaps.concat(),
+        }
+    }
+
+    /// Amount of multisets of dice in the table.
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Whether the table answers for games with `num_dices` dice played by
+    /// `rules`. The order of the operators and multipliers does not matter.
+    pub fn fits(&self, num_dices: usize, rules: &Rules) -> bool {
+        let sorted = |rules: &Rules| {
+            (
+                rules.operators.iter().copied().sorted().collect::<Vec<_>>(),
+                rules
+                    .multipliers
+                    .iter()
+                    .copied()
+                    .sorted()
+                    .collect::<Vec<_>>(),
+            )
+        };
+
+        self.num_dices == num_dices
+            && self.rules.rational == rules.rational
+            && self.rules.negative == rules.negative
+            && self.rules.die == rules.die
+            && sorted(&self.rules) == sorted(rules)
+    }
+
+    /// Whether `target` can be reached with `dices`, `None` if the table
+    /// does not cover the target or the dice.
+    pub fn is_solvable(&self, target: u64, dices: &[u64]) -> Option<bool> {
+        if !self.range.contains(&target) {
+            return None;
+        }
+        let bit = (target - self.range.start) as usize;
+        let bitmap = self.bitmap(dices)?;
+
+        Some(bitmap[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+
+    /// Every target in the range of the table that can be reached with
+    /// `dices`, in ascending order. `None` if the table does not cover the
+    /// dice.
+    pub fn reachable(&self, dices: &[u64]) -> Option<Vec<u64>> {
+        let bitmap = self.bitmap(dices)?;
+        let reachable = self
+            .range
+            .clone()
+            .enumerate()
+            .filter(|(bit, _)| bitmap[bit / 64] & (1 << (bit % 64)) != 0)
+            .map(|(_, target)| target)
+            .collect();
+
+        Some(reachable)
+    }
+
+    fn bitmap(&self, dices: &[u64]) -> Option<&[u64]> {
+        let mut dices = dices.to_vec();
+        dices.sort();
+        let i = *self.index.get(&dices)?;
+
+        Some(&self.bits[i * self.words..(i + 1) * self.words])
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        self.write(&mut file)?;
+        file.flush()?;
+
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::read(&mut BufReader::new(File::open(path)?))
+    }
+
+    /// Writes the header and the bitmaps, all numbers in little endian.
+    pub fn write(&self, w: &mut impl Write) -> Result<()> {
+        let num_dices = u8::try_from(self.num_dices)
+            .map_err(|_| anyhow!("Zu viele Würfel für eine Tabelle"))?;
+
+        w.write_all(MAGIC)?;
+        w.write_all(&[
+            VERSION,
+            num_dices,
+            self.rules.rational as u8,
+            self.rules.negative as u8,
+        ])?;
+
+        w.write_all(&[self.rules.operators.len() as u8])?;
+        for op in &self.rules.operators {
+            w.write_all(&[op.name().len() as u8])?;
+            w.write_all(op.name().as_bytes())?;
+        }
+        write_numbers(w, &self.rules.multipliers)?;
+        write_numbers(w, self.rules.die.faces())?;
+        write_numbers(w, &[self.range.start, self.range.end])?;
+
+        for word in &self.bits {
+            w.write_all(&word.to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads a table written by [`Table::write`]. Headers of implausibly
+    /// large tables are rejected before reading the bitmaps, as are bytes
+    /// after the bitmaps.
+    pub fn read(r: &mut impl Read) -> Result<Self> {
+        let mut magic = [0; 4];
+        r.read_exact(&mut magic)?;
+        let [version, num_dices, rational, negative] = read_bytes(r)?;
+        if &magic != MAGIC || version != VERSION {
+            return Err(anyhow!("Keine Tabelle der erreichbaren Zahlen"));
+        }
+
+        let [operators] = read_bytes(r)?;
+        let operators = (0..operators)
+            .map(|_| {
+                let [len] = read_bytes(r)?;
+                let mut name = vec![0; len.into()];
+                r.read_exact(&mut name)?;
+                String::from_utf8(name)?.parse::<Operator>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let multipliers = read_numbers(r)?;
+        let die = Die::of(read_numbers(r)?)?;
+        let [start, end] = read_numbers(r)?[..] else {
+            return Err(anyhow!("Ungültiger Bereich in der Tabelle"));
+        };
+
+        let rules = Rules {
+            rational: rational != 0,
+            negative: negative != 0,
+            operators,
+            multipliers,
+            die,
+        };
+        let num_dices = num_dices.into();
+        let words = end.saturating_sub(start).div_ceil(64);
+        let count = multiset_count(num_dices, rules.die.faces().len())
+            .filter(|&count| count <= MAX_BITMAPS)
+            .ok_or_else(|| anyhow!("Zu viele Würfel in der Tabelle"))?;
+        let len = count
+            .checked_mul(words)
+            .and_then(|words| words.checked_mul(8))
+            .filter(|&len| len <= MAX_BYTES)
+            .ok_or_else(|| anyhow!("Zu großer Bereich in der Tabelle"))?;
+
+        // only allocates as much as the file actually holds
+        let mut bytes = Vec::new();
+        r.by_ref().take(len).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 != len {
+            return Err(anyhow!("Die Tabelle ist unvollständig"));
+        }
+        if r.read(&mut [0])? != 0 {
+            return Err(anyhow!("Unerwartete Daten am Ende der Tabelle"));
+        }
+
+        let multisets = multisets(num_dices, &rules.die);
+        let bits = bytes
+            .chunks_exact(8)
+            .map(|word| u64::from_le_bytes(word.try_into().unwrap()))
+            .collect();
+
+        Ok(Self {
+            num_dices,
+            rules,
+            range: start..end,
+            index: index(multisets),
+            words: words as usize,
+            bits,
+        })
+    }
+}
+
+/// Every multiset of `num_dices` faces of `die`, each sorted ascending.
+fn multisets(num_dices: usize, die: &Die) -> Vec<Vec<u64>> {
+    die.faces()
+        .iter()
+        .copied()
+        .combinations_with_replacement(num_dices)
+        .collect()
+}
+
+/// Amount of multisets of `num_dices` out of `faces` faces, `None` if it
+/// does not fit into a u64.
+fn multiset_count(num_dices: usize, faces: usize) -> Option<u64> {
+    // the binomial coefficient (faces + num_dices - 1) over num_dices, each
+    // step is one of (faces - 1 + i) over i
+    (1..=num_dices as u64).try_fold(1u64, |count, i| {
+        Some(count.checked_mul(faces as u64 - 1 + i)? / i)
+    })
+}
+
+fn index(multisets: Vec<Vec<u64>>) -> HashMap<Vec<u64>, usize> {
+    multisets
+        .into_iter()
+        .enumerate()
+        .map(|(i, dices)| (dices, i))
+        .collect()
+}
+
+fn read_bytes<const N: usize>(r: &mut impl Read) -> Result<[u8; N]> {
+    let mut bytes = [0; N];
+    r.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// A count followed by the numbers.
+fn write_numbers(w: &mut impl Write, numbers: &[u64]) -> Result<()> {
+    w.write_all(&(numbers.len() as u32).to_le_bytes())?;
+    for n in numbers {
+        w.write_all(&n.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn read_numbers(r: &mut impl Read) -> Result<Vec<u64>> {
+    let len = u32::from_le_bytes(read_bytes(r)?);
+    if len > MAX_NUMBERS {
+        return Err(anyhow!("Zu viele Zahlen in der Tabelle"));
+    }
+    (0..len)
+        .map(|_| Ok(u64::from_le_bytes(read_bytes(r)?)))
+        .collect()
+}
@@ -0,0 +1,253 @@
+use std::{cmp::Ordering, fmt::Debug, hash::Hash};
+
+/// Number type the solvers compute intermediate results with.
+///
+/// The arithmetic is checked: every operation returns `None` if its result
+/// is not representable or not allowed for the type, e.g. an inexact integer
+/// division.
+pub trait Value: Copy + Eq + Ord + Hash + Debug + Send + 'static {
+    /// The value of a die with its multiplier, if representable.
+    fn from_int(v: u64) -> Option<Self>;
+
+    /// The value as a non-negative integer, if it is one.
+    fn to_int(self) -> Option<u64>;
+
+    fn checked_add(self, other: Self) -> Option<Self>;
+
+    fn checked_sub(self, other: Self) -> Option<Self>;
+
+    fn checked_mul(self, other: Self) -> Option<Self>;
+
+    fn checked_div(self, other: Self) -> Option<Self>;
+
+    /// Remainder of the integer division, only defined for integers.
+    fn checked_rem(self, other: Self) -> Option<Self>;
+
+    /// Whether this is a non-zero integer multiple of ten. Dividing two of
+    /// them only repeats a division with smaller multipliers.
+    fn is_round(self) -> bool;
+
+    fn is_negative(self) -> bool;
+}
+
+macro_rules! impl_unsigned_value {
+    ($($t:ty),*) => {$(
+        impl Value for $t {
+            fn from_int(v: u64) -> Option<Self> {
+                <$t>::try_from(v).ok()
+            }
+
+            fn to_int(self) -> Option<u64> {
+                Some(self.into())
+            }
+
+            fn checked_add(self, other: Self) -> Option<Self> {
+                <$t>::checked_add(self, other)
+            }
+
+            fn checked_sub(self, other: Self) -> Option<Self> {
+                <$t>::checked_sub(self, other)
+            }
+
+            fn checked_mul(self, other: Self) -> Option<Self> {
+                <$t>::checked_mul(self, other)
+            }
+
+            fn checked_div(self, other: Self) -> Option<Self> {
+                let div = <$t>::checked_div(self, other)?;
+                if div * other == self {
+                    Some(div)
+                } else {
+                    None
+                }
+            }
+
+            fn checked_rem(self, other: Self) -> Option<Self> {
+                <$t>::checked_rem(self, other)
+            }
+
+            fn is_round(self) -> bool {
+                self > 0 && self % 10 == 0
+            }
+
+            fn is_negative(self) -> bool {
+                false
+            }
+        }
+    )*};
+}
+
+impl_unsigned_value!(u32, u64);
+
+impl Value for i64 {
+    fn from_int(v: u64) -> Option<Self> {
+        v.try_into().ok()
+    }
+
+    fn to_int(self) -> Option<u64> {
+        self.try_into().ok()
+    }
+
+    fn checked_add(self, other: Self) -> Option<Self> {
+        i64::checked_add(self, other)
+    }
+
+    fn checked_sub(self, other: Self) -> Option<Self> {
+        i64::checked_sub(self, other)
+    }
+
+    fn checked_mul(self, other: Self) -> Option<Self> {
+        i64::checked_mul(self, other)
+    }
+
+    fn checked_div(self, other: Self) -> Option<Self> {
+        let div = i64::checked_div(self, other)?;
+        if div * other == self {
+            Some(div)
+        } else {
+            None
+        }
+    }
+
+    fn checked_rem(self, other: Self) -> Option<Self> {
+        i64::checked_rem_euclid(self, other)
+    }
+
+    fn is_round(self) -> bool {
+        self != 0 && self % 10 == 0
+    }
+
+    fn is_negative(self) -> bool {
+        self < 0
+    }
+}
+
+/// Exact fraction, always stored in lowest terms with a positive
+/// denominator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    num: i64,
+    den: i64,
+}
+
+impl Rational {
+    /// Reduces `num / den`, if it is representable.
+    fn of(num: i128, den: i128) -> Option<Self> {
+        if den == 0 {
+            return None;
+        }
+
+        let g = gcd(num.unsigned_abs(), den.unsigned_abs()) as i128;
+        let (num, den) = if den < 0 {
+            (-num / g, -den / g)
+        } else {
+            (num / g, den / g)
+        };
+
+        Some(Self {
+            num: num.try_into().ok()?,
+            den: den.try_into().ok()?,
+        })
+    }
+
+    pub fn numerator(&self) -> i64 {
+        self.num
+    }
+
+    pub fn denominator(&self) -> i64 {
+        self.den
+    }
+}
+
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.max(1)
+}
+
+impl Value for Rational {
+    fn from_int(v: u64) -> Option<Self> {
+        Some(Self {
+            num: v.try_into().ok()?,
+            den: 1,
+        })
+    }
+
+    fn to_int(self) -> Option<u64> {
+        if self.den == 1 {
+            self.num.try_into().ok()
+        } else {
+            None
+        }
+    }
+
+    fn checked_add(self, other: Self) -> Option<Self> {
+        Self::of(
+            self.num as i128 * other.den as i128 + other.num as i128 * self.den as i128,
+            self.den as i128 * other.den as i128,
+        )
+    }
+
+    fn checked_sub(self, other: Self) -> Option<Self> {
+        Self::of(
+            self.num as i128 * other.den as i128 - other.num as i128 * self.den as i128,
+            self.den as i128 * other.den as i128,
+        )
+    }
+
+    fn checked_mul(self, other: Self) -> Option<Self> {
+        Self::of(
+            self.num as i128 * other.num as i128,
+            self.den as i128 * other.den as i128,
+        )
+    }
+
+    fn checked_div(self, other: Self) -> Option<Self> {
+        Self::of(
+            self.num as i128 * other.den as i128,
+            self.den as i128 * other.num as i128,
+        )
+    }
+
+    fn checked_rem(self, other: Self) -> Option<Self> {
+        if self.den != 1 || other.den != 1 {
+            return None;
+        }
+
+        Some(Self {
+            num: self.num.checked_rem_euclid(other.num)?,
+            den: 1,
+        })
+    }
+
+    fn is_round(self) -> bool {
+        self.den == 1 && self.num != 0 && self.num % 10 == 0
+    }
+
+    fn is_negative(self) -> bool {
+        self.num < 0
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.num as i128 * other.den as i128).cmp(&(other.num as i128 * self.den as i128))
+    }
+}
+
+impl std::fmt::Display for Rational {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
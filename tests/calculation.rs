@@ -0,0 +1,257 @@
+use std::{
+    cmp::Ordering,
+    collections::HashSet,
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use nums::{
+    calculation::Calculation,
+    difficulty::{Classic, DifficultyModel, Level, MentalArithmetic, Rating},
+    operator::Operator,
+    parser::parse,
+    rules::Rules,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+const OPERATORS: [Operator; 6] = [
+    Operator::Add,
+    Operator::Sub,
+    Operator::Mul,
+    Operator::Div,
+    Operator::Pow,
+    Operator::Sqrt,
+];
+
+/// A random calculation over few values, so that equal ones come up often.
+fn random_calculation(rng: &mut StdRng, depth: u32) -> Calculation {
+    if depth == 0 || rng.random_bool(0.3) {
+        return Calculation::Cube(rng.random_range(0..4), rng.random_range(1..=3));
+    }
+
+    let op = OPERATORS[rng.random_range(0..OPERATORS.len())];
+    if op.is_unary() {
+        Calculation::unary(op, random_calculation(rng, depth - 1))
+    } else {
+        Calculation::binary(
+            op,
+            random_calculation(rng, depth - 1),
+            random_calculation(rng, depth - 1),
+        )
+    }
+}
+
+/// An equal calculation with swapped commutative operands and other dice.
+fn mirror(calc: &Calculation, rng: &mut StdRng) -> Calculation {
+    match calc {
+        Calculation::Binary(op, a, b) if op.is_commutative() && rng.random_bool(0.5) => {
+            Calculation::binary(*op, mirror(b, rng), mirror(a, rng))
+        }
+        Calculation::Binary(op, a, b) => Calculation::binary(*op, mirror(a, rng), mirror(b, rng)),
+        Calculation::Unary(op, a) => Calculation::unary(*op, mirror(a, rng)),
+        Calculation::Cube(_, v) => Calculation::Cube(rng.random_range(0..4), *v),
+    }
+}
+
+fn hash_of(calc: &Calculation) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    calc.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn random_calculations(seed: u64, amount: usize) -> Vec<Calculation> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..amount)
+        .map(|_| random_calculation(&mut rng, 3))
+        .collect()
+}
+
+#[test]
+fn mirrored_calculations_are_equal() {
+    let mut rng = StdRng::seed_from_u64(1);
+    for calc in random_calculations(2, 1000) {
+        let mirrored = mirror(&calc, &mut rng);
+
+        assert_eq!(calc, mirrored);
+        assert_eq!(hash_of(&calc), hash_of(&mirrored), "{} {}", calc, mirrored);
+        assert_eq!(
+            calc.cmp(&mirrored),
+            Ordering::Equal,
+            "{} {}",
+            calc,
+            mirrored
+        );
+    }
+}
+
+#[test]
+fn equality_agrees_with_hash_and_order() {
+    let calcs = random_calculations(3, 300);
+    for a in &calcs {
+        for b in &calcs {
+            assert_eq!(a == b, a.cmp(b) == Ordering::Equal, "{} {}", a, b);
+            assert_eq!(a.cmp(b), b.cmp(a).reverse(), "{} {}", a, b);
+            if a == b {
+                assert_eq!(hash_of(a), hash_of(b), "{} {}", a, b);
+            }
+        }
+    }
+}
+
+#[test]
+fn order_is_total() {
+    let mut calcs = random_calculations(4, 300);
+    calcs.sort();
+
+    for (i, a) in calcs.iter().enumerate() {
+        for b in &calcs[i..] {
+            assert_ne!(a.cmp(b), Ordering::Greater, "{} {}", a, b);
+        }
+    }
+}
+
+#[test]
+fn hash_set_removes_equal_calculations() {
+    let mut rng = StdRng::seed_from_u64(5);
+    let calcs = random_calculations(6, 200);
+    let distinct = calcs
+        .iter()
+        .fold(Vec::new(), |mut distinct: Vec<&Calculation>, calc| {
+            if !distinct.contains(&calc) {
+                distinct.push(calc);
+            }
+            distinct
+        });
+
+    let set = calcs
+        .iter()
+        .flat_map(|calc| [calc.clone(), mirror(calc, &mut rng)])
+        .collect::<HashSet<_>>();
+
+    assert_eq!(set.len(), distinct.len());
+}
+
+#[test]
+fn reordered_chains_have_the_same_normal_form() {
+    let cube = |v| Calculation::Cube(0, v);
+    let left = Calculation::binary(
+        Operator::Add,
+        Calculation::binary(Operator::Add, cube(100), cube(20)),
+        cube(3),
+    );
+    let right = Calculation::binary(
+        Operator::Add,
+        cube(100),
+        Calculation::binary(Operator::Add, cube(3), cube(20)),
+    );
+
+    assert_ne!(left, right);
+    assert_eq!(left.normalize(), right.normalize());
+}
+
+#[test]
+fn evaluation_checks_every_step() {
+    let cube = |i, v| Calculation::Cube(i, v);
+    let inexact = Calculation::binary(Operator::Div, cube(0, 7), cube(1, 2));
+    let negative = Calculation::binary(Operator::Sub, cube(0, 2), cube(1, 7));
+    let overflow = Calculation::binary(Operator::Pow, cube(0, 10), cube(1, 30));
+
+    assert_eq!(inexact.evaluate::<u64>(), None);
+    assert_eq!(negative.evaluate::<u64>(), None);
+    assert_eq!(negative.evaluate::<i64>(), Some(-5));
+    assert_eq!(overflow.evaluate::<u64>(), None);
+}
+
+#[test]
+fn verification_rejects_invalid_solutions() {
+    let rules = Rules::default();
+    let cube = |i, v| Calculation::Cube(i, v);
+    let solution = Calculation::binary(Operator::Add, cube(0, 60), cube(1, 4));
+
+    assert!(solution.verify(64, &[6, 4], &rules).is_ok());
+    assert!(solution.verify(65, &[6, 4], &rules).is_err());
+    assert!(solution.verify(64, &[6, 4, 1], &rules).is_err());
+    assert!(solution.verify(64, &[5, 4], &rules).is_err());
+
+    let twice = Calculation::binary(Operator::Add, cube(0, 60), cube(0, 4));
+    assert!(twice.verify(64, &[6, 4], &rules).is_err());
+
+    let power = Calculation::binary(Operator::Pow, cube(0, 4), cube(1, 3));
+    assert!(power.verify(64, &[4, 3], &rules).is_err());
+}
+
+#[test]
+fn verification_follows_the_rules_for_negative_results() {
+    let dices = [3, 4, 2];
+    let rational = Rules {
+        rational: true,
+        ..Rules::default()
+    };
+    let solution = parse("(3 - 4) + 2", &dices, &rational).unwrap();
+
+    assert!(solution.verify(1, &dices, &rational).is_err());
+    assert!(solution.verify(1, &dices, &Rules::default()).is_err());
+    for rules in [
+        Rules {
+            negative: true,
+            ..rational
+        },
+        Rules {
+            negative: true,
+            ..Rules::default()
+        },
+    ] {
+        assert_eq!(solution.value(&rules).unwrap(), 1);
+        assert!(solution.verify(1, &dices, &rules).is_ok());
+    }
+}
+
+#[test]
+fn difficulty_models_rate_solutions() {
+    let rules = Rules::default();
+    let dices = [6, 6, 4, 3];
+    let round = parse("(60 / 6) + (40 * 3)", &dices, &rules).unwrap();
+    let carrying = parse("(400 - (60 / 6)) / 3", &dices, &rules).unwrap();
+
+    // 34 + 20 + 30 for the operators, 12 + 10 + 12 + 10 for the dice
+    assert_eq!(Classic.score(&round), 128);
+    assert_eq!(round.score(), 128);
+    assert!(MentalArithmetic.score(&round) < MentalArithmetic.score(&carrying));
+
+    // writing digits next to each other is easier than multiplying them,
+    // a power is a multiplication per step of the exponent
+    let rules = Rules {
+        operators: Operator::ALL.to_vec(),
+        ..Rules::default()
+    };
+    let mental = |input| MentalArithmetic.score(&parse(input, &[6, 4], &rules).unwrap());
+    assert!(mental("6 || 4") < mental("6 * 4"));
+    assert!(mental("6 * 4") < mental("6 ^ 4"));
+    assert!(mental("6 - 4") < mental("4 - 6"));
+}
+
+#[test]
+fn games_are_rated_by_their_solutions() {
+    let rules = Rules::default();
+    let dices = [6, 6, 4, 3];
+    let pairs = parse("(60 / 6) + (40 * 3)", &dices, &rules).unwrap();
+    let single = parse("((600 / 4) - 30) + 6", &dices, &rules).unwrap();
+
+    let rating = Rating::of([&pairs, &single], &Classic).unwrap();
+    assert_eq!(rating.solutions, 2);
+    assert_eq!(rating.min_score, pairs.score().min(single.score()));
+    assert_eq!(rating.spread, pairs.score().abs_diff(single.score()));
+    assert!(rating.balanced);
+
+    let rating = Rating::of([&single], &Classic).unwrap();
+    assert!(!rating.balanced);
+    assert!(rating.score > single.score());
+
+    // only the score of the solution is scaled to four dice, not the
+    // bonuses for a single solution and a small spread
+    let trivial = parse("60 / 6", &[6, 6], &rules).unwrap();
+    let rating = Rating::of([&trivial], &Classic).unwrap();
+    assert_eq!(rating.score, 2 * trivial.score() + 40 + 5);
+    assert_ne!(rating.level, Level::Expert);
+
+    assert_eq!(Rating::of([], &Classic), None);
+}
@@ -0,0 +1,63 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// Runs `nums` with `args`, declines every question and returns what it
+/// printed.
+fn run(args: &[&str]) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_main"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"n\nn\nn\n").unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// The printed game and the options that repeat it.
+fn game_and_options(output: &str) -> (&str, Vec<&str>) {
+    let start = output.find("Startwert").expect("the seed is printed");
+    let line = output[start..].lines().next().unwrap();
+    let options = line
+        .split_once("(mit ")
+        .and_then(|(_, rest)| rest.strip_suffix(" wiederholbar)"))
+        .unwrap();
+
+    (&output[..start], options.split(' ').collect())
+}
+
+#[test]
+fn printed_options_repeat_the_game() {
+    for args in [
+        vec!["--rational", "--seed", "5", "3"],
+        vec!["--level", "easy", "--seed", "5", "--negative", "3"],
+        vec![
+            "--seed",
+            "9",
+            "--die",
+            "1,2,3,5,8",
+            "--ops",
+            "add,sub,mul,div,pow",
+            "--multipliers",
+            "1,10",
+            "4",
+        ],
+    ] {
+        let output = run(&args);
+        let (game, options) = game_and_options(&output);
+        let mut repeated_args = options.clone();
+        repeated_args.push(args.last().unwrap());
+        let repeated = run(&repeated_args);
+        let (repeated_game, repeated_options) = game_and_options(&repeated);
+
+        assert_eq!(game, repeated_game, "{:?}", args);
+        assert_eq!(options, repeated_options, "{:?}", args);
+        for arg in &args[..args.len() - 1] {
+            assert!(options.contains(arg), "{} missing in {:?}", arg, options);
+        }
+    }
+}
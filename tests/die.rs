@@ -0,0 +1,48 @@
+use nums::die::Die;
+use rand::{rngs::StdRng, SeedableRng};
+
+#[test]
+fn dice_are_parsed_and_displayed() {
+    for (spec, sides) in [("d4", 4), ("d6", 6), ("d8", 8), ("d12", 12), ("d20", 20)] {
+        let die = spec.parse::<Die>().unwrap();
+        assert_eq!(die, Die::sides(sides));
+        assert_eq!(die.faces(), (1..=sides).collect::<Vec<_>>());
+    }
+    let d10 = "d10".parse::<Die>().unwrap();
+    assert_eq!(d10.faces(), (0..=9).collect::<Vec<_>>());
+
+    let custom = "8, 1,2,3,5,3".parse::<Die>().unwrap();
+    assert_eq!(custom.faces(), [1, 2, 3, 5, 8]);
+    assert_eq!(custom.to_string(), "[1,2,3,5,8]");
+
+    for die in [Die::d4(), Die::d6(), d10, Die::d20(), custom] {
+        assert_eq!(die.to_string().parse::<Die>().unwrap(), die);
+    }
+}
+
+#[test]
+fn bad_dice_are_rejected() {
+    for spec in ["", "d7", "d", "6d", "1,,2", "1,-2", "[1,2"] {
+        assert!(spec.parse::<Die>().is_err(), "{}", spec);
+    }
+    assert!(Die::of(Vec::new()).is_err());
+}
+
+#[test]
+fn dice_only_show_their_faces() {
+    let d6 = Die::d6();
+    assert!(d6.check(&[1, 6, 3]).is_ok());
+    let error = d6.check(&[1, 7]).unwrap_err().to_string();
+    assert!(error.contains("Würfel2 zeigt 7"), "{}", error);
+    assert!(d6.check(&[0]).is_err());
+
+    let d10 = Die::d10();
+    assert!(d10.check(&[0, 9]).is_ok());
+    assert!(d10.check(&[10]).is_err());
+
+    let mut rng = StdRng::seed_from_u64(1);
+    let custom = Die::of(vec![1, 2, 3, 5, 8]).unwrap();
+    for _ in 0..100 {
+        assert!(custom.has_face(custom.roll(&mut rng)));
+    }
+}
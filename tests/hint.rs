@@ -0,0 +1,69 @@
+use nums::{
+    difficulty::{Classic, MentalArithmetic},
+    hint::hints,
+    operator::Operator,
+    parser::parse,
+    rules::Rules,
+};
+
+#[test]
+fn hints_reveal_more_each_time() {
+    let rules = Rules::default();
+    let solution = parse("(400 - 6) - (60 / 3)", &[4, 6, 6, 3], &rules).unwrap();
+
+    assert_eq!(
+        hints(&solution, &Classic),
+        [
+            "Der letzte Rechenschritt ist eine Subtraktion (-)",
+            "Ein Zwischenergebnis ist 20",
+            "Ein Teil der Lösung ist (60 / 3) = 20",
+            "Die Lösung ist ((400 - 6) - (60 / 3))",
+        ]
+    );
+
+    // mental arithmetic rates the subtraction with a borrow harder than the
+    // round division
+    assert_eq!(
+        hints(&solution, &MentalArithmetic)[1..3],
+        [
+            "Ein Zwischenergebnis ist 394",
+            "Ein Teil der Lösung ist (400 - 6) = 394",
+        ]
+    );
+}
+
+#[test]
+fn hints_skip_what_a_die_shows() {
+    let rules = Rules::default();
+
+    let solution = parse("(6 * 4) + 3", &[6, 4, 3], &rules).unwrap();
+    assert_eq!(hints(&solution, &Classic).len(), 4);
+
+    // dice show their value anyway, they are no intermediate result
+    let solution = parse("6 * 40", &[6, 4], &rules).unwrap();
+    assert_eq!(
+        hints(&solution, &Classic),
+        [
+            "Der letzte Rechenschritt ist eine Multiplikation (*)",
+            "Die Lösung ist (6 * 40)",
+        ]
+    );
+
+    let rules = Rules {
+        operators: Operator::ALL.to_vec(),
+        ..Rules::default()
+    };
+    let solution = parse("4! - 6", &[4, 6], &rules).unwrap();
+    assert_eq!(
+        hints(&solution, &Classic)[1..3],
+        [
+            "Ein Zwischenergebnis ist 24",
+            "Ein Teil der Lösung ist 4! = 24"
+        ]
+    );
+    let solution = parse("3!", &[3], &rules).unwrap();
+    assert_eq!(hints(&solution, &Classic).len(), 2);
+
+    let solution = parse("6", &[6], &rules).unwrap();
+    assert_eq!(hints(&solution, &Classic), ["Die Lösung ist 6"]);
+}
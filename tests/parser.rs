@@ -0,0 +1,107 @@
+use nums::{die::Die, operator::Operator, parser::parse, rules::Rules};
+
+fn error(input: &str, dices: &[u64], rules: &Rules) -> String {
+    parse(input, dices, rules).unwrap_err().to_string()
+}
+
+#[test]
+fn parses_solutions_with_precedence() {
+    let rules = Rules::default();
+    let dices = [6, 6, 4, 3];
+
+    for input in [
+        "((60 / 6) + (40 * 3))",
+        "60 / 6 + 40 * 3",
+        "60 : 6 + 40 x 3",
+        "60÷6 + 40×3",
+    ] {
+        let calc = parse(input, &dices, &rules).unwrap();
+        assert_eq!(calc.to_string(), "((60 / 6) + (40 * 3))", "{}", input);
+        assert!(calc.verify(130, &dices, &rules).is_ok(), "{}", input);
+    }
+
+    let calc = parse("2 ^ 3 ^ 2", &[2, 3, 2], &rules).unwrap();
+    assert_eq!(calc.to_string(), "(2 ^ (3 ^ 2))");
+}
+
+#[test]
+fn parses_unary_operators() {
+    let rules = Rules {
+        operators: Operator::ALL.to_vec(),
+        ..Rules::default()
+    };
+
+    let calc = parse("√400 + 3! + 2", &[4, 3, 2], &rules).unwrap();
+    assert_eq!(calc.to_string(), "((√400 + 3!) + 2)");
+    assert!(calc.verify(28, &[4, 3, 2], &rules).is_ok());
+}
+
+#[test]
+fn maps_numbers_to_free_dice() {
+    let rules = Rules::default();
+
+    let calc = parse("30 + 3", &[3, 3], &rules).unwrap();
+    assert!(calc.verify(33, &[3, 3], &rules).is_ok());
+
+    // 10 is either die, 100 only the second, in any order
+    let rules = Rules {
+        die: Die::d12(),
+        multipliers: vec![1, 10],
+        ..Rules::default()
+    };
+    for input in ["100 + 10", "10 + 100"] {
+        let calc = parse(input, &[1, 10], &rules).unwrap();
+        assert!(calc.verify(110, &[1, 10], &rules).is_ok(), "{}", input);
+    }
+    assert_eq!(
+        error("10 + 100 + 1", &[1, 10], &rules),
+        "Würfel1 (1) wird doppelt benutzt"
+    );
+}
+
+#[test]
+fn reports_precise_errors() {
+    let rules = Rules::default();
+    let dices = [6, 4, 3];
+
+    assert_eq!(
+        error("60 + 7 + 3", &dices, &rules),
+        "Zu 7 gibt es keinen Würfel"
+    );
+    assert_eq!(
+        error("60 + 6 + 3", &dices, &rules),
+        "Würfel1 (6) wird doppelt benutzt"
+    );
+    assert_eq!(
+        error(
+            "60 + 4 + 3",
+            &dices,
+            &Rules {
+                multipliers: vec![1],
+                ..Rules::default()
+            }
+        ),
+        "Faktor 10 ist für 6 nicht erlaubt"
+    );
+    assert_eq!(
+        error("(6 / 4) * 300", &dices, &rules),
+        "Division (6 / 4) geht nicht auf"
+    );
+    assert!(parse(
+        "(6 / 4) * 300",
+        &dices,
+        &Rules {
+            rational: true,
+            ..Rules::default()
+        }
+    )
+    .is_ok());
+    assert_eq!(
+        error("(60 + 4 * 3", &dices, &rules),
+        "Erwartet ')' am Ende der Eingabe"
+    );
+    assert_eq!(
+        error("60 + 4 3", &dices, &rules),
+        "Unerwartetes Zeichen '3' an Stelle 8"
+    );
+}
@@ -0,0 +1,38 @@
+use nums::{
+    game::{Answer, Game},
+    rules::Rules,
+};
+
+#[test]
+fn answers_follow_the_rules_of_the_game() {
+    let mut game = Game {
+        rules: Rules {
+            rational: true,
+            ..Rules::default()
+        },
+        ..Game::of(1, vec![3, 4, 2])
+    };
+
+    let error = game.check_answer("(3 - 4) + 2").unwrap_err();
+    assert!(error.to_string().contains("negativ"), "{}", error);
+    assert!(matches!(
+        game.check_answer("(4 - 3) * 2"),
+        Ok(Answer::Wrong { value: 2, .. })
+    ));
+
+    // every solution the solver finds is accepted as an answer
+    game.solve();
+    assert!(!game.solutions.is_empty());
+    for solution in game.solutions.clone() {
+        assert!(matches!(
+            game.check_answer(&solution.to_string()),
+            Ok(Answer::Correct(_))
+        ));
+    }
+
+    game.rules.negative = true;
+    assert!(matches!(
+        game.check_answer("(3 - 4) + 2"),
+        Ok(Answer::Correct(_))
+    ));
+}
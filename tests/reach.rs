@@ -0,0 +1,97 @@
+use nums::{die::Die, game::Game, operator::Operator, reach::Reach, rules::Rules, solver::Solver};
+
+#[test]
+fn reach_agrees_with_the_solver() {
+    let variants = [
+        Rules::default(),
+        Rules {
+            negative: true,
+            ..Rules::default()
+        },
+        Rules {
+            rational: true,
+            multipliers: vec![1, 10],
+            ..Rules::default()
+        },
+        Rules {
+            operators: vec![
+                Operator::Add,
+                Operator::Mul,
+                Operator::Sqrt,
+                Operator::Factorial,
+            ],
+            multipliers: vec![1, 10],
+            ..Rules::default()
+        },
+    ];
+
+    for rules in variants {
+        let reach = Reach::new(rules.clone());
+        let mut game = Game {
+            rules: rules.clone(),
+            ..Game::default()
+        };
+        for dices in [&[6, 4, 3][..], &[1, 1, 2, 5]] {
+            let coverage = reach.coverage(0..1000, dices);
+            let reachable = game.reachable(0..1000, dices);
+            assert_eq!(coverage.len(), reachable.len(), "{:?} {:?}", rules, dices);
+            assert!(coverage.iter().eq(reachable), "{:?} {:?}", rules, dices);
+        }
+    }
+}
+
+#[test]
+fn solvers_find_a_solution_for_every_reachable_target() {
+    let all = Rules {
+        operators: Operator::ALL.to_vec(),
+        multipliers: vec![1, 10],
+        die: Die::d10(),
+        ..Rules::default()
+    };
+    let variants = [
+        all.clone(),
+        Rules {
+            negative: true,
+            ..all.clone()
+        },
+        Rules {
+            rational: true,
+            ..all.clone()
+        },
+    ];
+
+    // a 0 operand, and targets that are only hit by a square root or a
+    // factorial in the final step, like √49 and 3!
+    for rules in variants {
+        let reach = Reach::new(rules.clone());
+        let mut game = Game {
+            rules: rules.clone(),
+            ..Game::default()
+        };
+        let mut fgame = nums::fastgame::Game::new();
+        fgame.rules = rules.clone();
+
+        for dices in [&[0, 7, 7][..], &[3, 0, 2]] {
+            let coverage = reach.coverage(0..100, dices);
+            for target in 0..100 {
+                let reachable = coverage.contains(target);
+                assert_eq!(
+                    !game.solve_for(target, dices).is_empty(),
+                    reachable,
+                    "{:?} {} {:?}",
+                    rules,
+                    target,
+                    dices
+                );
+                assert_eq!(
+                    !fgame.solve_for(target, dices).is_empty(),
+                    reachable,
+                    "{:?} {} {:?}",
+                    rules,
+                    target,
+                    dices
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,361 @@
+use std::{
+    collections::HashSet,
+    time::{Duration, Instant},
+};
+
+use nums::{
+    calculation::Calculation,
+    daily,
+    difficulty::Level,
+    fastgame,
+    game::{Game, MAX_ATTEMPTS},
+    parser::parse,
+    rules::Rules,
+    solver::{CancelToken, Closest, Solver},
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+#[test]
+fn solvers_only_find_valid_solutions() {
+    let dices = [6, 6, 4, 3];
+    let rules = Rules::default();
+
+    let mut game = Game::default();
+    let mut fgame = fastgame::Game::new();
+    for num in [130, 271, 999] {
+        for solution in game.solve_for(num, &dices) {
+            assert!(solution.verify(num, &dices, &rules).is_ok(), "{}", solution);
+        }
+        for solution in fgame.solve_for(num, &dices) {
+            assert!(solution.verify(num, &dices, &rules).is_ok(), "{}", solution);
+        }
+    }
+}
+
+#[test]
+fn round_divisions_are_kept_without_smaller_multipliers() {
+    let rules = Rules {
+        multipliers: vec![10, 100],
+        ..Rules::default()
+    };
+    assert!(!rules.has_all_powers_of_ten());
+    assert!(Rules::default().has_all_powers_of_ten());
+
+    let expected = parse("60 / 60", &[6, 6], &rules).unwrap();
+    let mut game = Game {
+        rules: rules.clone(),
+        ..Game::default()
+    };
+    let mut fgame = fastgame::Game::new();
+    fgame.rules = rules;
+    assert!(game.solve_for(1, &[6, 6]).contains(&expected));
+    assert!(fgame.solve_for(1, &[6, 6]).contains(&expected));
+}
+
+#[test]
+fn generated_games_match_the_level() {
+    for level in [Level::Easy, Level::Hard] {
+        let mut rng = StdRng::seed_from_u64(7);
+        let game = Game::generate(
+            3,
+            level,
+            Rules::default(),
+            500,
+            None,
+            &CancelToken::new(),
+            &mut rng,
+        );
+        let rating = game.rating().expect("generated game is solvable");
+        assert_eq!(rating.level, level);
+        assert!(!game.missed_level());
+    }
+
+    // expert games with three dice are rare, a single attempt misses them
+    let mut rng = StdRng::seed_from_u64(7);
+    let game = Game::generate(
+        3,
+        Level::Expert,
+        Rules::default(),
+        1,
+        None,
+        &CancelToken::new(),
+        &mut rng,
+    );
+    assert_eq!(game.level, Some(Level::Expert));
+    assert!(game.missed_level());
+}
+
+#[test]
+fn seeded_games_are_reproducible() {
+    let first = Game::seeded(4, Rules::default(), 42);
+    let second = Game::seeded(4, Rules::default(), 42);
+    assert_eq!((first.num, &first.dices), (second.num, &second.dices));
+
+    let mut first = Game::daily(3, Rules::default(), 20_000, &CancelToken::new());
+    let mut second = Game::daily(3, Rules::default(), 20_000, &CancelToken::new());
+    first.solve();
+    second.solve();
+    assert_eq!((first.num, &first.dices), (second.num, &second.dices));
+    assert_eq!(first.solutions, second.solutions);
+    assert_eq!(daily::date(20_000), "2024-10-04");
+
+    // the printed seed and level repeat the puzzle of the day
+    let seed = first.seed.unwrap();
+    let repeated = Game::generate(
+        3,
+        first.level.unwrap(),
+        Rules::default(),
+        MAX_ATTEMPTS,
+        None,
+        &CancelToken::new(),
+        &mut StdRng::seed_from_u64(seed),
+    );
+    assert_eq!((first.num, &first.dices), (repeated.num, &repeated.dices));
+}
+
+#[test]
+fn solutions_are_streamed() {
+    let mut game = Game::of(999, vec![1, 2, 3, 4]);
+    let first = game.solutions_iter().take(2).collect::<Vec<_>>();
+    assert_eq!(first.len(), 2);
+
+    let streamed = game.solutions_iter().collect::<HashSet<_>>();
+    game.solve();
+    assert_eq!(streamed, game.solutions.iter().cloned().collect());
+    assert!(first.iter().all(|solution| streamed.contains(solution)));
+}
+
+#[test]
+fn dropped_streams_only_cancel_their_own_search() {
+    let parent = CancelToken::new();
+    let child = parent.child();
+    child.cancel();
+    assert!(child.is_cancelled());
+    assert!(!parent.is_cancelled());
+    parent.cancel();
+    assert!(parent.child().is_cancelled());
+
+    let mut game = Game::of(999, vec![1, 2, 3, 4]);
+    assert_eq!(game.solutions_iter().take(1).count(), 1);
+    assert!(!game.cancel.is_cancelled());
+    game.solve();
+    assert!(!game.incomplete);
+    assert!(!game.solutions.is_empty());
+}
+
+#[test]
+fn cancelled_search_is_incomplete() {
+    let mut game = Game::of(999, vec![1, 2, 3, 4]);
+    game.solve();
+    assert!(!game.incomplete);
+    assert!(!game.solutions.is_empty());
+
+    game.cancel = CancelToken::new();
+    game.cancel.clone().cancel();
+    game.solve();
+    assert!(game.incomplete);
+    assert!(game.solutions.is_empty());
+    assert!(game.reachable(0..1000, &[1, 2, 3, 4]).len() < 1000);
+    assert!(game.incomplete);
+
+    let mut fgame = fastgame::Game::new();
+    fgame.cancel = CancelToken::with_deadline(Instant::now());
+    fgame.solve(999, &[1, 2, 3, 4]);
+    assert!(fgame.incomplete);
+    assert!(fgame.solutions.is_empty());
+
+    // generating a game stops too
+    let game = Game::generate(
+        4,
+        Level::Hard,
+        Rules::default(),
+        MAX_ATTEMPTS,
+        None,
+        &fgame.cancel,
+        &mut StdRng::seed_from_u64(1),
+    );
+    assert!(game.incomplete);
+    assert!(game.missed_level());
+
+    fgame.cancel = CancelToken::with_timeout(Duration::from_secs(3600));
+    fgame.solve(999, &[1, 2, 3, 4]);
+    assert!(!fgame.incomplete);
+    assert!(!fgame.solutions.is_empty());
+}
+
+#[test]
+fn closest_results_are_not_repeated() {
+    let dices = [6, 6, 4, 3];
+    let normal_forms = |closest: &Closest| {
+        closest
+            .results
+            .iter()
+            .map(|(_, calc)| calc.normalize())
+            .collect::<HashSet<_>>()
+    };
+
+    let mut game = Game::of(271, dices.to_vec());
+    game.solve();
+    assert!(game.solutions.is_empty());
+    assert_eq!(game.closest.distance, Some(1));
+    assert_eq!(
+        normal_forms(&game.closest).len(),
+        game.closest.results.len()
+    );
+
+    let mut fgame = fastgame::Game::new();
+    fgame.solve(271, &[6, 6, 4, 3]);
+    assert!(!fgame.closest.is_empty());
+    assert_eq!(
+        normal_forms(&fgame.closest).len(),
+        fgame.closest.results.len()
+    );
+
+    let mut raw = Game {
+        keep_equivalent: true,
+        ..Game::of(271, dices.to_vec())
+    };
+    raw.solve();
+    assert!(raw.closest.results.len() > game.closest.results.len());
+}
+
+#[test]
+fn solvers_handle_any_dice_count() {
+    let small = Rules {
+        multipliers: vec![1],
+        ..Rules::default()
+    };
+    for (num, dices, rules) in [
+        (24, vec![6, 4], Rules::default()),
+        (64, vec![6, 4], Rules::default()),
+        (120, vec![1, 2, 3, 4, 5], small.clone()),
+        (721, vec![1, 2, 3, 4, 5, 6], small),
+    ] {
+        let mut game = Game {
+            rules: rules.clone(),
+            ..Game::of(num, dices.clone())
+        };
+        let mut fgame = fastgame::Game::new();
+        fgame.rules = rules.clone();
+
+        for solutions in [game.solve_for(num, &dices), fgame.solve_for(num, &dices)] {
+            assert!(!solutions.is_empty(), "{} with {:?}", num, dices);
+            for solution in solutions {
+                assert!(solution.verify(num, &dices, &rules).is_ok(), "{}", solution);
+            }
+        }
+    }
+}
+
+#[test]
+fn all_solutions_keep_every_derivation() {
+    let mut game = Game::of(999, vec![1, 2, 3, 4]);
+    game.solve();
+    let mut all = Game {
+        all_solutions: true,
+        ..Game::of(999, vec![1, 2, 3, 4])
+    };
+    all.solve();
+
+    assert_eq!(game.solutions.len(), 8);
+    assert_eq!(all.raw_solution_count, 84);
+    assert!(all.raw_solution_count > game.raw_solution_count);
+    assert!(all.solutions.len() > game.solutions.len());
+    for solution in &all.solutions {
+        assert!(
+            solution.verify(999, &all.dices, &all.rules).is_ok(),
+            "{}",
+            solution
+        );
+    }
+
+    let normal_forms = all
+        .solutions
+        .iter()
+        .map(|solution| solution.normalize())
+        .collect::<HashSet<_>>();
+    assert!(game
+        .solutions
+        .iter()
+        .all(|solution| normal_forms.contains(&solution.normalize())));
+}
+
+#[test]
+fn rational_rules_reach_more_targets() {
+    let dices = [1, 3, 4, 6];
+    let rules = Rules {
+        multipliers: vec![1],
+        ..Rules::default()
+    };
+    let rational = Rules {
+        rational: true,
+        ..rules.clone()
+    };
+    let expected = parse("6 / (1 - (3 / 4))", &dices, &rational).unwrap();
+
+    let mut game = Game {
+        rules: rules.clone(),
+        ..Game::default()
+    };
+    let mut fgame = fastgame::Game::new();
+    fgame.rules = rules;
+    assert!(game.solve_for(24, &dices).is_empty());
+    assert!(fgame.solve_for(24, &dices).is_empty());
+
+    game.rules = rational.clone();
+    fgame.rules = rational.clone();
+    for solutions in [game.solve_for(24, &dices), fgame.solve_for(24, &dices)] {
+        assert!(solutions.contains(&expected));
+        for solution in solutions {
+            assert!(
+                solution.verify(24, &dices, &rational).is_ok(),
+                "{}",
+                solution
+            );
+        }
+    }
+}
+
+#[test]
+fn negative_rules_allow_negative_intermediate_results() {
+    let dices = [3, 4, 2];
+    let rules = Rules::default();
+    let negative = Rules {
+        negative: true,
+        ..Rules::default()
+    };
+    let expected = parse("(3 - 4) + 2", &dices, &negative).unwrap();
+    let has_negative_step = |solution: &Calculation| solution.value(&rules).is_err();
+
+    for (rules, allowed) in [(rules.clone(), false), (negative, true)] {
+        let mut game = Game {
+            rules: rules.clone(),
+            keep_equivalent: true,
+            ..Game::default()
+        };
+        let mut fgame = fastgame::Game::new();
+        fgame.rules = rules.clone();
+        fgame.keep_equivalent = true;
+
+        for solutions in [game.solve_for(1, &dices), fgame.solve_for(1, &dices)] {
+            assert_eq!(solutions.contains(&expected), allowed);
+            assert_eq!(solutions.iter().any(has_negative_step), allowed);
+            for solution in solutions {
+                assert!(solution.verify(1, &dices, &rules).is_ok(), "{}", solution);
+            }
+        }
+    }
+}
+
+#[test]
+fn large_targets_are_solved() {
+    let num = 60_000_000 * 60_000_000;
+    let mut game = Game::default();
+    let mut fgame = fastgame::Game::new();
+
+    assert!(!game.solve_for(num, &[6, 6]).is_empty());
+    assert!(!fgame.solve_for(num, &[6, 6]).is_empty());
+    assert!(fgame.is_reachable(num, &[6, 6]));
+    assert!(!game.is_reachable(u64::MAX, &[6, 6]));
+    assert!(!fgame.is_reachable(u64::MAX, &[6, 6]));
+}
@@ -0,0 +1,78 @@
+use nums::{game::Game, operator::Operator, rules::Rules, solver::Solver, table::Table};
+
+#[test]
+fn table_agrees_with_the_solver() {
+    let rules = Rules::default();
+    let table = Table::build(3, rules.clone(), 0..100);
+    assert_eq!(table.len(), 56);
+
+    let mut game = Game {
+        rules: rules.clone(),
+        ..Game::default()
+    };
+    for dices in [[1, 1, 1], [6, 5, 4], [2, 6, 2]] {
+        assert_eq!(
+            table.reachable(&dices),
+            Some(game.reachable(0..100, &dices))
+        );
+        for target in [0, 7, 53, 99] {
+            assert_eq!(
+                table.is_solvable(target, &dices),
+                Some(game.is_reachable(target, &dices)),
+                "{} {:?}",
+                target,
+                dices
+            );
+        }
+    }
+
+    assert_eq!(table.is_solvable(100, &[1, 2, 3]), None);
+    assert_eq!(table.is_solvable(10, &[1, 2, 7]), None);
+    assert_eq!(table.is_solvable(10, &[1, 2]), None);
+}
+
+#[test]
+fn table_survives_a_round_trip() {
+    let rules = Rules {
+        operators: vec![Operator::Mul, Operator::Add, Operator::Sqrt],
+        multipliers: vec![1, 10],
+        ..Rules::default()
+    };
+    let table = Table::build(2, rules.clone(), 5..75);
+
+    let mut bytes = Vec::new();
+    table.write(&mut bytes).unwrap();
+    let read = Table::read(&mut bytes.as_slice()).unwrap();
+    assert_eq!(read, table);
+
+    let reordered = Rules {
+        operators: vec![Operator::Sqrt, Operator::Add, Operator::Mul],
+        ..rules.clone()
+    };
+    assert!(read.fits(2, &reordered));
+    assert!(!read.fits(3, &rules));
+    assert!(!read.fits(2, &Rules::default()));
+
+    assert!(Table::read(&mut &bytes[..bytes.len() - 1]).is_err());
+    assert!(Table::read(&mut &b"gaps"[..]).is_err());
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(Table::read(&mut trailing.as_slice()).is_err());
+
+    // 255 dice, as if the file was corrupt
+    let mut corrupt = bytes.clone();
+    corrupt[5] = u8::MAX;
+    assert!(Table::read(&mut corrupt.as_slice()).is_err());
+
+    // a range end of u64::MAX, right before the 21 bitmaps of 2 words
+    let mut huge = bytes.clone();
+    let end = bytes.len() - 21 * 2 * 8 - 8;
+    assert_eq!(bytes[end..end + 8], 75u64.to_le_bytes());
+    huge[end..end + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert!(Table::read(&mut huge.as_slice()).is_err());
+
+    let mut too_many = table.clone();
+    too_many.num_dices = 256;
+    assert!(too_many.write(&mut Vec::new()).is_err());
+}